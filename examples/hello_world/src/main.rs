@@ -5,7 +5,7 @@ use std::{
 
 use sdl3_sys::{
     error::SDL_GetError,
-    events::{SDL_Event, SDL_EventType, SDL_PollEvent},
+    events::{SDL_Event, SDL_EventType, SDL_PollEvent, SDL_WaitEventTimeout},
     init::{SDL_INIT_VIDEO, SDL_Init},
     keycode::SDLK_ESCAPE,
     pixels::SDL_ALPHA_OPAQUE,
@@ -23,6 +23,7 @@ pub fn main() -> Result<(), &'static CStr> {
     let mut window: *mut SDL_Window = null_mut();
     let mut editor_text = String::new();
     let mut color_picker = [0.0, 0.0, 0.0, 1.0];
+    let mut gradient_texture: Option<egui::TextureHandle> = None;
 
     // All calls to SDL are unsafe
     unsafe {
@@ -45,27 +46,15 @@ pub fn main() -> Result<(), &'static CStr> {
     let mut painter = egui_sdl3::Painter::new(window);
 
     'main_loop: loop {
-        // UPDATE
-        let ticks = unsafe { SDL_GetTicks() };
-        painter.update_time(ticks as f64 / 1000.0);
-        let ctx = painter.begin_pass();
-        egui::Window::new("Hello, world!").show(&ctx, |ui| {
-            ui.label("Hello, world!");
-            if ui.button("Greet").clicked() {
-                println!("Hello, world!");
-            }
-            ui.horizontal(|ui| {
-                ui.label("Color: ");
-                ui.color_edit_button_rgba_premultiplied(&mut color_picker);
-            });
-            ui.code_editor(&mut editor_text);
-        });
-        painter.end_pass();
-
         // INPUT
+        // Sleep until there is input or egui wants another pass, e.g. for an animation or a
+        // tooltip, instead of spinning
+        let timeout = painter.repaint_after().as_millis().min(i32::MAX as u128) as i32;
         unsafe {
             let mut input_event = SDL_Event::default();
-            while SDL_PollEvent(std::ptr::addr_of_mut!(input_event)) {
+            let mut waited = SDL_WaitEventTimeout(addr_of_mut!(input_event), timeout);
+            while waited || SDL_PollEvent(addr_of_mut!(input_event)) {
+                waited = false;
                 if painter.handle_event(input_event, window) {
                     continue;
                 }
@@ -84,16 +73,59 @@ pub fn main() -> Result<(), &'static CStr> {
             }
         }
 
+        // UPDATE
+        let ticks = unsafe { SDL_GetTicks() };
+        painter.update_time(ticks as f64 / 1000.0);
+        let ctx = painter.begin_pass();
+        egui::Window::new("Hello, world!").show(&ctx, |ui| {
+            ui.label("Hello, world!");
+            if ui.button("Greet").clicked() {
+                println!("Hello, world!");
+            }
+            ui.horizontal(|ui| {
+                ui.label("Color: ");
+                ui.color_edit_button_rgba_premultiplied(&mut color_picker);
+            });
+            ui.code_editor(&mut editor_text);
+
+            // The same gradient from vertex colors and from a texture, these only look the same
+            // if the painter's color space matches the renderer
+            ui.label("Vertex and texture gradients:");
+            let size = egui::vec2(256.0, 16.0);
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            let mut mesh = egui::Mesh::default();
+            mesh.colored_vertex(rect.left_top(), egui::Color32::BLACK);
+            mesh.colored_vertex(rect.right_top(), egui::Color32::WHITE);
+            mesh.colored_vertex(rect.left_bottom(), egui::Color32::BLACK);
+            mesh.colored_vertex(rect.right_bottom(), egui::Color32::WHITE);
+            mesh.add_triangle(0, 1, 2);
+            mesh.add_triangle(1, 2, 3);
+            ui.painter().add(mesh);
+            let texture = gradient_texture.get_or_insert_with(|| {
+                let pixels = (0..=255).map(egui::Color32::from_gray).collect();
+                ctx.load_texture(
+                    "gradient",
+                    egui::ColorImage::new([256, 1], pixels),
+                    egui::TextureOptions::LINEAR,
+                )
+            });
+            ui.image((texture.id(), size));
+        });
+        painter.end_pass();
+
         // RENDER
-        unsafe {
-            SDL_SetRenderDrawColor(renderer, 245, 245, 245, SDL_ALPHA_OPAQUE);
-            SDL_RenderClear(renderer);
-        }
+        // The last presented frame stays on screen while nothing changed
+        if painter.needs_repaint() {
+            unsafe {
+                SDL_SetRenderDrawColor(renderer, 245, 245, 245, SDL_ALPHA_OPAQUE);
+                SDL_RenderClear(renderer);
+            }
 
-        painter.draw(renderer);
+            painter.draw(renderer);
 
-        unsafe {
-            SDL_RenderPresent(renderer);
+            unsafe {
+                SDL_RenderPresent(renderer);
+            }
         }
     }
 
@@ -0,0 +1,45 @@
+use crate::Painter;
+use egui::epaint::Primitive;
+use serde::{Deserialize, Serialize};
+
+/* A mesh as egui tessellated it, in points. */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CapturedMesh {
+    pub clip_rect: egui::Rect,
+    pub mesh: egui::Mesh,
+}
+
+/* Everything `Painter::draw` would submit for a frame, for comparing against a stored golden.
+ * Paint callbacks are left out, they can't be drawn by this painter either. */
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameCapture {
+    pub pixels_per_point: f32,
+    pub meshes: Vec<CapturedMesh>,
+    pub textures: egui::TexturesDelta,
+}
+
+impl Painter {
+    /* Copies the frame produced by the last `end_pass`, which is still drawn by the next `draw`
+     * as usual. None if there is no frame waiting to be drawn. */
+    pub fn debug_capture_frame(&self) -> Option<FrameCapture> {
+        let draw_info = self.draw_info.as_ref()?;
+        let meshes = draw_info
+            .primitives
+            .iter()
+            .filter_map(|primitive| match &primitive.primitive {
+                Primitive::Mesh(mesh) => Some(CapturedMesh {
+                    clip_rect: primitive.clip_rect,
+                    mesh: mesh.clone(),
+                }),
+                Primitive::Callback(_) => None,
+            })
+            .collect();
+        Some(FrameCapture {
+            pixels_per_point: self
+                .render_scale
+                .unwrap_or_else(|| self.ctx.pixels_per_point()),
+            meshes,
+            textures: draw_info.textures.clone(),
+        })
+    }
+}
@@ -0,0 +1,37 @@
+use crate::Painter;
+use egui::epaint::text::{FontInsert, FontPriority, InsertFontFamily};
+
+impl Painter {
+    /* Looks up the regular face of the installed font `family`, e.g. "Segoe UI" or "Cantarell",
+     * and makes it the first choice for proportional text. egui's default fonts stay as the
+     * fallback for missing glyphs. Returns false, keeping the current fonts, if the family isn't
+     * installed. Scanning the system fonts is slow, so this is best called once at startup. */
+    pub fn use_system_font(&mut self, family: &str) -> bool {
+        let mut db = ::fontdb::Database::new();
+        db.load_system_fonts();
+        let query = ::fontdb::Query {
+            families: &[::fontdb::Family::Name(family)],
+            ..Default::default()
+        };
+        let Some(font) = db.query(&query).and_then(|id| {
+            db.with_face_data(id, |data, index| {
+                let mut font = egui::FontData::from_owned(data.to_vec());
+                font.index = index;
+                font
+            })
+        }) else {
+            log::warn!("System font {family:?} not found");
+            return false;
+        };
+
+        self.ctx.add_font(FontInsert::new(
+            family,
+            font,
+            vec![InsertFontFamily {
+                family: egui::FontFamily::Proportional,
+                priority: FontPriority::Highest,
+            }],
+        ));
+        true
+    }
+}
@@ -0,0 +1,52 @@
+use crate::Painter;
+use sdl3_sys::video::SDL_GL_GetProcAddress;
+use std::sync::Arc;
+
+pub use egui_glow::glow;
+
+/* Renders egui through OpenGL for windows created with an OpenGL context instead of an
+ * SDL_Renderer. Input is still handled by `Painter`, this only replaces `Painter::draw`. */
+pub struct GlowPainter {
+    painter: egui_glow::Painter,
+}
+
+impl GlowPainter {
+    /* SAFETY: The GL context must be current on the calling thread. */
+    pub fn new(gl: Arc<glow::Context>) -> Result<Self, egui_glow::PainterError> {
+        let painter = egui_glow::Painter::new(gl, "", None, false)?;
+        Ok(Self { painter })
+    }
+
+    pub fn gl(&self) -> &Arc<glow::Context> {
+        self.painter.gl()
+    }
+
+    /* SAFETY: The GL context must be current on the calling thread. */
+    pub fn draw(&mut self, painter: &mut Painter) {
+        let Some((textures, primitives)) = painter.take_frame() else {
+            return;
+        };
+        let size = painter.screen_size_pixels;
+        self.painter.paint_and_update_textures(
+            [size.x as u32, size.y as u32],
+            painter.ctx.pixels_per_point(),
+            &primitives,
+            &textures,
+        );
+        painter.last_drawn = primitives;
+    }
+
+    /* SAFETY: Must be called before the GL context is destroyed. */
+    pub fn destroy(&mut self) {
+        self.painter.destroy();
+    }
+}
+
+/* SAFETY: An OpenGL context created by SDL must be current on the calling thread. */
+pub fn load_gl_context() -> glow::Context {
+    unsafe {
+        glow::Context::from_loader_function_cstr(|name| {
+            SDL_GL_GetProcAddress(name.as_ptr()).map_or(std::ptr::null(), |f| f as *const _)
+        })
+    }
+}
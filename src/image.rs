@@ -0,0 +1,97 @@
+use egui::load::{BytesPoll, ImageLoadResult, ImageLoader, ImagePoll, LoadError, SizeHint};
+use egui::mutex::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+type Entry = Result<Arc<egui::ColorImage>, String>;
+
+/* Decodes images for egui with the `image` crate. `file://` URIs are read from disk, anything
+ * else goes through egui's bytes loaders, e.g. for `include_image!`. The decoded images are
+ * uploaded by `Painter::draw` like any other egui texture. */
+#[derive(Default)]
+pub struct ImageCrateLoader {
+    cache: Mutex<HashMap<String, Entry>>,
+}
+
+impl ImageCrateLoader {
+    pub const ID: &'static str = egui::load::generate_loader_id!(ImageCrateLoader);
+}
+
+/* Installs the loader on the context if it isn't already. */
+pub fn install_image_loader(ctx: &egui::Context) {
+    if !ctx.is_loader_installed(ImageCrateLoader::ID) {
+        ctx.add_image_loader(Arc::new(ImageCrateLoader::default()));
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<Entry, LoadError> {
+    let image = match ::image::load_from_memory(bytes) {
+        Ok(image) => image,
+        // Let other loaders try formats the image crate wasn't built with
+        Err(::image::ImageError::Unsupported(_)) => return Err(LoadError::NotSupported),
+        Err(e) => return Ok(Err(e.to_string())),
+    };
+    let rgba = image.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(Ok(Arc::new(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        rgba.as_raw(),
+    ))))
+}
+
+impl ImageLoader for ImageCrateLoader {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn load(&self, ctx: &egui::Context, uri: &str, _: SizeHint) -> ImageLoadResult {
+        if let Some(entry) = self.cache.lock().get(uri) {
+            return match entry {
+                Ok(image) => Ok(ImagePoll::Ready {
+                    image: image.clone(),
+                }),
+                Err(e) => Err(LoadError::Loading(e.clone())),
+            };
+        }
+
+        let entry = if let Some(path) = uri.strip_prefix("file://") {
+            match std::fs::read(path) {
+                Ok(bytes) => decode(&bytes)?,
+                Err(e) => Err(format!("{path}: {e}")),
+            }
+        } else {
+            match ctx.try_load_bytes(uri)? {
+                BytesPoll::Ready { bytes, .. } => decode(&bytes)?,
+                BytesPoll::Pending { size } => return Ok(ImagePoll::Pending { size }),
+            }
+        };
+        if let Err(e) = &entry {
+            log::warn!("Failed to load image {uri}: {e}");
+        }
+
+        self.cache.lock().insert(uri.to_owned(), entry.clone());
+        match entry {
+            Ok(image) => Ok(ImagePoll::Ready { image }),
+            Err(e) => Err(LoadError::Loading(e)),
+        }
+    }
+
+    fn forget(&self, uri: &str) {
+        self.cache.lock().remove(uri);
+    }
+
+    fn forget_all(&self) {
+        self.cache.lock().clear();
+    }
+
+    fn byte_size(&self) -> usize {
+        self.cache
+            .lock()
+            .values()
+            .map(|entry| match entry {
+                Ok(image) => image.pixels.len() * std::mem::size_of::<egui::Color32>(),
+                Err(e) => e.len(),
+            })
+            .sum()
+    }
+}
@@ -1,27 +1,46 @@
 use egui::epaint::Primitive;
 use egui::{OutputCommand, TextureId};
 use sdl3_sys::error::SDL_GetError;
-use sdl3_sys::events::{SDL_Event, SDL_EventType};
-use sdl3_sys::keyboard::{SDL_GetModState, SDL_StartTextInput, SDL_StopTextInput};
-use sdl3_sys::keycode::SDL_Keycode;
-use sdl3_sys::mouse::{SDL_CreateSystemCursor, SDL_Cursor, SDL_DestroyCursor, SDL_SystemCursor};
+use sdl3_sys::events::{SDL_Event, SDL_EventType, SDL_MouseButtonEvent, SDL_TouchFingerEvent};
+use sdl3_sys::keyboard::{
+    SDL_GetModState, SDL_StartTextInput, SDL_StopTextInput, SDL_TextInputActive,
+};
+use sdl3_sys::keycode::{SDL_Keycode, SDL_Keymod};
+use sdl3_sys::mouse::{
+    SDL_CreateSystemCursor, SDL_Cursor, SDL_DestroyCursor, SDL_MouseID, SDL_SystemCursor,
+};
 use sdl3_sys::pixels::SDL_FColor;
-use sdl3_sys::rect::{SDL_FPoint, SDL_Rect};
+use sdl3_sys::properties::SDL_GetPointerProperty;
+use sdl3_sys::rect::{SDL_FPoint, SDL_FRect, SDL_Rect};
 use sdl3_sys::render::{
     SDL_CreateTexture, SDL_DestroyTexture, SDL_GetRenderScale, SDL_SetRenderScale, SDL_Texture,
     SDL_UpdateTexture, SDL_Vertex,
 };
 use sdl3_sys::stdinc::SDL_free;
-use sdl3_sys::video::{SDL_GetWindowSize, SDL_GetWindowSizeInPixels, SDL_Window};
-use sdl3_sys::{clipboard, keycode, mouse, pixels, render};
+use sdl3_sys::surface::{SDL_ConvertSurface, SDL_CreateSurfaceFrom, SDL_DestroySurface};
+use sdl3_sys::touch::{SDL_FingerID, SDL_MOUSE_TOUCHID, SDL_TOUCH_MOUSEID};
+use sdl3_sys::video::{
+    SDL_GetSystemTheme, SDL_GetWindowSize, SDL_GetWindowSizeInPixels, SDL_SetWindowIcon,
+    SDL_SystemTheme, SDL_Window,
+};
+use sdl3_sys::{
+    clipboard, hints, keycode, locale, messagebox, mouse, pixels, render, timer, video,
+};
 use std::collections::HashMap;
 use std::ffi::CStr;
 use std::ptr;
-use std::ptr::addr_of_mut;
+
+#[cfg(feature = "debug-capture")]
+pub mod capture;
+#[cfg(feature = "fontdb")]
+mod fontdb;
+#[cfg(feature = "glow")]
+pub mod glow;
+#[cfg(feature = "image")]
+pub mod image;
 
 struct Cursor {
     ptr: *mut SDL_Cursor,
-    looks: SDL_SystemCursor,
 }
 impl Cursor {
     /* SAFETY: This needs to be called from main thread */
@@ -31,7 +50,7 @@ impl Cursor {
             if ptr.is_null() {
                 return Err(CStr::from_ptr(SDL_GetError()));
             }
-            Ok(Self { ptr, looks })
+            Ok(Self { ptr })
         }
     }
 }
@@ -46,6 +65,607 @@ impl Drop for Cursor {
     }
 }
 
+/* Work submitted by the most recent `Painter::draw` call. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub meshes: usize,
+    pub vertices: usize,
+    pub indices: usize,
+    pub textures_created: usize,
+    pub textures_updated: usize,
+    pub textures_freed: usize,
+    /* SDL_RenderGeometryRaw calls after batching, compare with `meshes` */
+    pub draw_calls: usize,
+    /* Meshes drawn as plain rectangles with `Painter::set_fill_rect_fast_path`, which are part
+     * of `meshes` but not of `vertices` and `indices` */
+    pub fill_rects: usize,
+}
+
+/* Time spent on the most recent pass, see `Painter::set_measure_timing`. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PassTiming {
+    /* From the start of `Painter::begin_pass` until egui finished the pass in
+     * `Painter::end_pass`, which includes the application's UI code */
+    pub pass: std::time::Duration,
+    /* Turning the shapes of the pass into meshes */
+    pub tessellation: std::time::Duration,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockState {
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+/* Where copied text goes and pasted text comes from, for environments where SDL's clipboard
+ * doesn't work, or for tests. */
+pub trait Clipboard {
+    fn get_text(&mut self) -> Option<String>;
+    fn set_text(&mut self, text: &str);
+}
+
+/* The system clipboard through SDL, used unless another clipboard is set. Only usable from the
+ * main thread. */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SdlClipboard;
+
+impl Clipboard for SdlClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        unsafe {
+            if !clipboard::SDL_HasClipboardText() {
+                return None;
+            }
+            let text = clipboard::SDL_GetClipboardText();
+            if text.is_null() {
+                return None;
+            }
+            let result = CStr::from_ptr(text).to_str().ok().map(str::to_owned);
+            SDL_free(text as *mut _);
+            result
+        }
+    }
+
+    fn set_text(&mut self, text: &str) {
+        if let Ok(text) = std::ffi::CString::new(text) {
+            unsafe {
+                if !clipboard::SDL_SetClipboardText(text.as_ptr()) {
+                    log::error!("Failed to set clipboard text: {}", sdl_error());
+                };
+            }
+        }
+    }
+}
+
+/* One of the user's preferred locales, most preferred first in `Painter::locales`. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Locale {
+    /* A language code such as "en" or "ar" */
+    pub language: String,
+    /* A country code such as "US", if the system has one */
+    pub country: Option<String>,
+}
+
+impl Locale {
+    /* Whether the language is written right to left. */
+    pub fn is_rtl(&self) -> bool {
+        matches!(
+            self.language.as_str(),
+            "ar" | "dv" | "fa" | "he" | "ku" | "ps" | "sd" | "ug" | "ur" | "yi"
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScaleMode {
+    /* Use the window's display scale as is, e.g. 1.5 on a 150% display */
+    #[default]
+    Fractional,
+    /* Round to the nearest integer scale for crisp pixel-art UIs */
+    Integer,
+}
+
+impl ScaleMode {
+    fn apply(self, pixels_per_point: f32) -> f32 {
+        match self {
+            ScaleMode::Fractional => pixels_per_point,
+            ScaleMode::Integer => pixels_per_point.round().max(1.0),
+        }
+    }
+}
+
+/* How mouse input is treated while relative mouse mode is enabled on the window, where motion
+ * coordinates no longer track a visible cursor. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RelativeMouseMode {
+    /* Ignore the mouse so mouse-look doesn't drive egui */
+    #[default]
+    Suspend,
+    /* Move the egui pointer by the relative motion, for an egui drawn software cursor */
+    Accumulate,
+}
+
+/* Cursor image drawn by `draw` while the software cursor is enabled. */
+struct SoftwareCursor {
+    icon: egui::CursorIcon,
+    image: egui::ColorImage,
+    /* In pixels of the image, from the top left */
+    hotspot: egui::Vec2,
+    texture: *mut SDL_Texture,
+}
+
+impl SoftwareCursor {
+    fn arrow() -> Self {
+        const ARROW: [&str; 17] = [
+            "X..........",
+            "XX.........",
+            "XoX........",
+            "XooX.......",
+            "XoooX......",
+            "XooooX.....",
+            "XoooooX....",
+            "XooooooX...",
+            "XoooooooX..",
+            "XooooooooX.",
+            "XoooooXXXXX",
+            "XooXooX....",
+            "XoX.XooX...",
+            "XX..XooX...",
+            "X....XooX..",
+            ".....XooX..",
+            "......XX...",
+        ];
+        let pixels = ARROW
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|c| match c {
+                'X' => egui::Color32::BLACK,
+                'o' => egui::Color32::WHITE,
+                _ => egui::Color32::TRANSPARENT,
+            })
+            .collect();
+        Self {
+            icon: egui::CursorIcon::Default,
+            image: egui::ColorImage::new([ARROW[0].len(), ARROW.len()], pixels),
+            hotspot: egui::Vec2::ZERO,
+            texture: ptr::null_mut(),
+        }
+    }
+}
+
+/* How the colors of an image passed to `register_texture` relate to its alpha. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    /* Colors are already multiplied by alpha, as egui blends, e.g. images made with
+     * `ColorImage::from_rgba_unmultiplied`. Uploaded as is. */
+    #[default]
+    Premultiplied,
+    /* The pixels hold plain RGBA values, which are premultiplied while uploading */
+    Straight,
+}
+
+/* What `draw` clears the render target with before drawing egui. */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Background {
+    /* Leave clearing to the application */
+    #[default]
+    None,
+    /* The panel color of the current egui visuals, following theme changes */
+    PanelFill,
+    Color(egui::Color32),
+}
+
+/* The color space vertex colors are handed to SDL in. Textures are unaffected, SDL converts
+ * them from their own color space. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /* egui's colors as is. SDL_Renderer takes sRGB colors, also when rendering to a linear
+     * output color space, so this matches textures out of the box. */
+    #[default]
+    Srgb,
+    /* Converted to linear values, for pipelines that expect linear vertex colors */
+    Linear,
+}
+
+/* One SDL draw call, prepared from egui's primitives by `draw_commands` without touching SDL
+ * and then executed by `Painter::draw`. */
+enum DrawCommand {
+    Geometry(GeometryBatch),
+    FillRects(FillBatch),
+}
+
+/* The painter settings that decide how primitives turn into draw commands. */
+struct DrawOptions {
+    pixels_per_point: f32,
+    opacity: f32,
+    color_space: ColorSpace,
+    fill_rect_fast_path: bool,
+    /* Textures with uploads still waiting for a later frame, which aren't drawn until then */
+    deferred_textures: Vec<TextureId>,
+}
+
+#[derive(Default)]
+struct GeometryBatch {
+    clip: SDL_Rect,
+    texture: TextureId,
+    vertices: Vec<SDL_Vertex>,
+    /* Only one of these is used at a time. A batch is kept within the 16 bit range unless a
+     * single mesh needs more. */
+    indices16: Vec<u16>,
+    indices32: Vec<u32>,
+}
+
+impl GeometryBatch {
+    fn is_empty(&self) -> bool {
+        self.indices16.is_empty() && self.indices32.is_empty()
+    }
+
+    /* Whether `mesh` can be added without going past the 16 bit index range. */
+    fn fits(&self, mesh: &egui::Mesh) -> bool {
+        self.vertices.len() + mesh.vertices.len() <= u16::MAX as usize + 1
+    }
+
+    fn push(
+        &mut self,
+        mesh: &egui::Mesh,
+        pixels_per_point: f32,
+        opacity: f32,
+        color_space: ColorSpace,
+    ) {
+        let offset = self.vertices.len() as u32;
+        if self.indices32.is_empty() && self.fits(mesh) {
+            self.indices16
+                .extend(mesh.indices.iter().map(|&i| (offset + i) as u16));
+        } else {
+            self.indices32
+                .extend(mesh.indices.iter().map(|&i| offset + i));
+        }
+        self.vertices
+            .extend(mesh.vertices.iter().map(|v| SDL_Vertex {
+                position: SDL_FPoint {
+                    x: v.pos.x * pixels_per_point,
+                    y: v.pos.y * pixels_per_point,
+                },
+                color: vertex_color(v.color, opacity, color_space),
+                tex_coord: SDL_FPoint {
+                    x: v.uv.x,
+                    y: v.uv.y,
+                },
+            }));
+    }
+
+    fn index(&self, i: usize) -> usize {
+        if self.indices32.is_empty() {
+            self.indices16[i] as usize
+        } else {
+            self.indices32[i] as usize
+        }
+    }
+
+    fn index_count(&self) -> usize {
+        self.indices16.len() + self.indices32.len()
+    }
+
+    /* Moves the batch into `commands` if there is anything in it, keeping the clip rect and
+     * texture for the meshes that follow. */
+    fn finish(&mut self, commands: &mut Vec<DrawCommand>) {
+        if self.is_empty() {
+            return;
+        }
+        commands.push(DrawCommand::Geometry(GeometryBatch {
+            clip: self.clip,
+            texture: self.texture,
+            vertices: std::mem::take(&mut self.vertices),
+            indices16: std::mem::take(&mut self.indices16),
+            indices32: std::mem::take(&mut self.indices32),
+        }));
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn execute(
+        &self,
+        renderer: *mut render::SDL_Renderer,
+        texture: *mut SDL_Texture,
+        stats: &mut FrameStats,
+        geometry_supported: bool,
+    ) -> bool {
+        unsafe { render::SDL_SetRenderClipRect(renderer, &self.clip) };
+        let mut ok = false;
+        if geometry_supported {
+            let (indices, size_indices) = if self.indices32.is_empty() {
+                (self.indices16.as_ptr().cast(), size_of::<u16>())
+            } else {
+                (self.indices32.as_ptr().cast(), size_of::<u32>())
+            };
+            let stride = size_of::<SDL_Vertex>() as i32;
+            let vertices = self.vertices.as_ptr();
+            ok = unsafe {
+                render::SDL_RenderGeometryRaw(
+                    renderer,
+                    texture,
+                    &raw const (*vertices).position.x,
+                    stride,
+                    &raw const (*vertices).color,
+                    stride,
+                    &raw const (*vertices).tex_coord.x,
+                    stride,
+                    self.vertices.len() as i32,
+                    indices,
+                    self.index_count() as i32,
+                    size_indices as i32,
+                )
+            };
+            if ok {
+                stats.draw_calls += 1;
+            } else {
+                // The renderer supports geometry, so this can be temporary and the next frame
+                // tries again
+                log::debug!(
+                    "SDL_RenderGeometryRaw failed, drawing rectangles: {}",
+                    sdl_error()
+                );
+            }
+        }
+        if !ok {
+            ok = self.render_rects(renderer, texture, stats);
+        }
+        ok
+    }
+
+    /* Draws the axis aligned quads in the batch, which covers most of egui's widget backgrounds
+     * and all text, with plain rect calls. Anything else is dropped.
+     * SAFETY: This needs to be called from main thread */
+    fn render_rects(
+        &self,
+        renderer: *mut render::SDL_Renderer,
+        texture: *mut SDL_Texture,
+        stats: &mut FrameStats,
+    ) -> bool {
+        let mut texture_size = (0.0, 0.0);
+        if !texture.is_null() {
+            unsafe {
+                render::SDL_GetTextureSize(texture, &mut texture_size.0, &mut texture_size.1)
+            };
+        }
+        unsafe {
+            render::SDL_SetRenderDrawBlendMode(
+                renderer,
+                sdl3_sys::blendmode::SDL_BLENDMODE_BLEND_PREMULTIPLIED,
+            )
+        };
+
+        let mut ok = true;
+        for quad in 0..self.index_count() / 6 {
+            let Some((dst, uv_min, uv_max, color)) = self.rect_at(quad * 6) else {
+                continue;
+            };
+            ok &= unsafe {
+                if texture.is_null() {
+                    render::SDL_SetRenderDrawColorFloat(
+                        renderer, color.r, color.g, color.b, color.a,
+                    );
+                    render::SDL_RenderFillRect(renderer, &dst)
+                } else {
+                    // Plain shapes sample a single white texel, which would be an empty rect
+                    let mut src = SDL_FRect {
+                        x: uv_min.x * texture_size.0,
+                        y: uv_min.y * texture_size.1,
+                        w: (uv_max.x - uv_min.x) * texture_size.0,
+                        h: (uv_max.y - uv_min.y) * texture_size.1,
+                    };
+                    if src.w < 1.0 {
+                        src.x += src.w / 2.0 - 0.5;
+                        src.w = 1.0;
+                    }
+                    if src.h < 1.0 {
+                        src.y += src.h / 2.0 - 0.5;
+                        src.h = 1.0;
+                    }
+                    render::SDL_SetTextureColorModFloat(texture, color.r, color.g, color.b);
+                    render::SDL_SetTextureAlphaModFloat(texture, color.a);
+                    render::SDL_RenderTexture(renderer, texture, &src, &dst)
+                }
+            };
+            stats.draw_calls += 1;
+        }
+
+        if !texture.is_null() {
+            unsafe {
+                render::SDL_SetTextureColorModFloat(texture, 1.0, 1.0, 1.0);
+                render::SDL_SetTextureAlphaModFloat(texture, 1.0);
+            }
+        }
+        ok
+    }
+
+    /* The rectangle drawn by the two triangles starting at index `first`, along with its
+     * texture coordinates and color. None unless the triangles share a diagonal and cover an
+     * axis aligned rectangle in one color, with the texture mapped without rotation or flips,
+     * which is how egui builds text and most widget backgrounds. Indices can't be assumed to
+     * come in quads, so anything else is left out rather than drawn wrong. */
+    fn rect_at(&self, first: usize) -> Option<(SDL_FRect, SDL_FPoint, SDL_FPoint, SDL_FColor)> {
+        let a = [0, 1, 2].map(|i| self.index(first + i));
+        let b = [3, 4, 5].map(|i| self.index(first + i));
+        let shared: Vec<usize> = a.iter().copied().filter(|i| b.contains(i)).collect();
+        let other_a = a.iter().copied().find(|i| !shared.contains(i));
+        let other_b = b.iter().copied().find(|i| !shared.contains(i));
+        let (&[p, q], Some(r), Some(s)) = (shared.as_slice(), other_a, other_b) else {
+            return None;
+        };
+        if r == s {
+            return None;
+        }
+        let [p, q, r, s] = [p, q, r, s].map(|i| &self.vertices[i]);
+
+        let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+        let same = |a: SDL_FPoint, b: SDL_FPoint| close(a.x, b.x) && close(a.y, b.y);
+        // The shared edge is the diagonal from p to q, r and s are the two other corners
+        let corner = |v: &SDL_Vertex, x: &SDL_Vertex, y: &SDL_Vertex| {
+            let position = SDL_FPoint {
+                x: x.position.x,
+                y: y.position.y,
+            };
+            let tex_coord = SDL_FPoint {
+                x: x.tex_coord.x,
+                y: y.tex_coord.y,
+            };
+            same(v.position, position) && same(v.tex_coord, tex_coord)
+        };
+        let corners = (corner(r, p, q) && corner(s, q, p)) || (corner(r, q, p) && corner(s, p, q));
+        let (dx, dy) = (q.position.x - p.position.x, q.position.y - p.position.y);
+        let (du, dv) = (q.tex_coord.x - p.tex_coord.x, q.tex_coord.y - p.tex_coord.y);
+        let unflipped = du * dx >= 0.0 && dv * dy >= 0.0;
+        let one_color = [q, r, s].iter().all(|v| v.color == p.color);
+        if !corners || !unflipped || !one_color || close(dx, 0.0) || close(dy, 0.0) {
+            return None;
+        }
+
+        let (min, max) = if dx > 0.0 { (p, q) } else { (q, p) };
+        let (top, bottom) = if dy > 0.0 { (p, q) } else { (q, p) };
+        let dst = SDL_FRect {
+            x: min.position.x,
+            y: top.position.y,
+            w: dx.abs(),
+            h: dy.abs(),
+        };
+        let uv_min = SDL_FPoint {
+            x: min.tex_coord.x,
+            y: top.tex_coord.y,
+        };
+        let uv_max = SDL_FPoint {
+            x: max.tex_coord.x,
+            y: bottom.tex_coord.y,
+        };
+        Some((dst, uv_min, uv_max, p.color))
+    }
+}
+
+/* The renderer state `draw` changes, so egui can be drawn on top of a scene without affecting
+ * what the application renders next. */
+struct RenderState {
+    scale: (f32, f32),
+    viewport: Option<SDL_Rect>,
+    clip: Option<SDL_Rect>,
+    blend_mode: sdl3_sys::blendmode::SDL_BlendMode,
+    draw_color: (f32, f32, f32, f32),
+}
+
+impl RenderState {
+    /* SAFETY: This needs to be called from main thread */
+    fn save(renderer: *mut render::SDL_Renderer) -> Self {
+        let mut state = Self {
+            scale: (1.0, 1.0),
+            viewport: None,
+            clip: None,
+            blend_mode: sdl3_sys::blendmode::SDL_BLENDMODE_NONE,
+            draw_color: (0.0, 0.0, 0.0, 0.0),
+        };
+        unsafe {
+            SDL_GetRenderScale(renderer, &mut state.scale.0, &mut state.scale.1);
+            if render::SDL_RenderViewportSet(renderer) {
+                let mut viewport = SDL_Rect::default();
+                render::SDL_GetRenderViewport(renderer, &mut viewport);
+                state.viewport = Some(viewport);
+            }
+            if render::SDL_RenderClipEnabled(renderer) {
+                let mut clip = SDL_Rect::default();
+                render::SDL_GetRenderClipRect(renderer, &mut clip);
+                state.clip = Some(clip);
+            }
+            render::SDL_GetRenderDrawBlendMode(renderer, &mut state.blend_mode);
+            let (r, g, b, a) = &mut state.draw_color;
+            render::SDL_GetRenderDrawColorFloat(renderer, r, g, b, a);
+        }
+        state
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn restore(&self, renderer: *mut render::SDL_Renderer) {
+        let (r, g, b, a) = self.draw_color;
+        unsafe {
+            // Null means the whole target, which keeps following its size
+            match &self.viewport {
+                Some(viewport) => render::SDL_SetRenderViewport(renderer, viewport),
+                None => render::SDL_SetRenderViewport(renderer, ptr::null()),
+            };
+            SDL_SetRenderScale(renderer, self.scale.0, self.scale.1);
+            match &self.clip {
+                Some(clip) => render::SDL_SetRenderClipRect(renderer, clip),
+                None => render::SDL_SetRenderClipRect(renderer, ptr::null()),
+            };
+            render::SDL_SetRenderDrawBlendMode(renderer, self.blend_mode);
+            render::SDL_SetRenderDrawColorFloat(renderer, r, g, b, a);
+        }
+    }
+}
+
+/* Solid rectangles of one color and clip rect, drawn with a single SDL_RenderFillRects call. */
+struct FillBatch {
+    clip: SDL_Rect,
+    color: SDL_FColor,
+    rects: Vec<SDL_FRect>,
+}
+
+impl Default for FillBatch {
+    fn default() -> Self {
+        Self {
+            clip: SDL_Rect::default(),
+            color: SDL_FColor {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.0,
+            },
+            rects: Vec::new(),
+        }
+    }
+}
+
+impl FillBatch {
+    fn finish(&mut self, commands: &mut Vec<DrawCommand>) {
+        if self.rects.is_empty() {
+            return;
+        }
+        commands.push(DrawCommand::FillRects(FillBatch {
+            clip: self.clip,
+            color: self.color,
+            rects: std::mem::take(&mut self.rects),
+        }));
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn execute(&self, renderer: *mut render::SDL_Renderer, stats: &mut FrameStats) -> bool {
+        let color = self.color;
+        let ok = unsafe {
+            render::SDL_SetRenderClipRect(renderer, &self.clip);
+            render::SDL_SetRenderDrawBlendMode(
+                renderer,
+                sdl3_sys::blendmode::SDL_BLENDMODE_BLEND_PREMULTIPLIED,
+            );
+            render::SDL_SetRenderDrawColorFloat(renderer, color.r, color.g, color.b, color.a);
+            render::SDL_RenderFillRects(renderer, self.rects.as_ptr(), self.rects.len() as i32)
+        };
+        stats.draw_calls += 1;
+        ok
+    }
+}
+
+type FatalErrorHook = Box<dyn FnMut(&str)>;
+type WidgetEventHook = Box<dyn FnMut(&egui::output::OutputEvent)>;
+type SetupHook = Box<dyn FnOnce(&egui::Context)>;
+
+/* Memory use of a texture from `register_texture`, and the `draw` that last used it */
+struct UserTexture {
+    bytes: usize,
+    last_used: u64,
+}
+
+/* What the last frame looked like, to find what changed in the next one */
+struct DamageState {
+    pixels_per_point: f32,
+    screen_rect: egui::Rect,
+    primitives: Vec<egui::ClippedPrimitive>,
+}
+
 struct DrawInfo {
     textures: egui::TexturesDelta,
     primitives: Vec<egui::ClippedPrimitive>,
@@ -53,357 +673,2058 @@ struct DrawInfo {
 
 pub struct Painter {
     ctx: egui::Context,
-    cursor: Cursor,
+    window: *mut SDL_Window,
+    /* Failed cursors are kept as None so they aren't retried on every change */
+    cursors: HashMap<SDL_SystemCursor, Option<Cursor>>,
+    cursor_looks: SDL_SystemCursor,
+    /* The icon `cursor_looks` was picked for, None when it has to be set again */
+    applied_cursor_icon: Option<egui::CursorIcon>,
+    cursor_icon: egui::CursorIcon,
+    manage_cursor: bool,
+    manage_text_input: bool,
+    cursor_hidden: bool,
+    software_cursor: bool,
+    software_cursors: Vec<SoftwareCursor>,
     cursor_pos: egui::Pos2,
+    relative_mouse_mode: RelativeMouseMode,
+    pressed_buttons: Vec<egui::PointerButton>,
+    // SDL's double click radius in window coordinates, converted to points every pass
+    double_click_radius: Option<f32>,
+    force_line_scroll: bool,
+    trackpad_pinch: bool,
+    ctrl_is_command: bool,
+    scroll_smoothing: Option<f32>,
+    pending_scroll: egui::Vec2,
+    input_enabled: bool,
+    zoom_range: (f32, f32),
+    mouse_device: Option<SDL_MouseID>,
+    prefer_touch: bool,
+    follow_system_theme: bool,
+    text_input_active: bool,
+    wants_text_input: bool,
+    text_received: bool,
+    text_consumed: bool,
+    ime_composing: bool,
+    repaint_forced: bool,
+    needs_repaint: bool,
+    repaint_after: std::time::Duration,
+    /* The frame last handed to a renderer, to tell whether a new frame looks any different */
+    last_drawn: Vec<egui::ClippedPrimitive>,
+    last_pixels_per_point: f32,
+    pointer_finger: Option<SDL_FingerID>,
+    locales: Vec<Locale>,
+    /* In window coordinates, None while SDL doesn't know of one */
+    safe_area: Option<SDL_Rect>,
+    scale_mode: ScaleMode,
+    pixel_density: f32,
+    /* The scale the system asks for on top of the pixel density, e.g. 1.5 on a 150% display
+     * set up on Windows, where pixels and window coordinates are the same */
+    content_scale: f32,
+    /* The size is tracked in pixels and only turned into the points egui lays out in when a
+     * pass starts, as the zoom factor may have changed since. */
+    window_size: egui::Vec2,
+    screen_size_pixels: egui::Vec2,
     modifiers: egui::Modifiers,
     raw_input: egui::RawInput,
+    clipboard: Box<dyn Clipboard>,
+    /* The renderer the textures were created with, null until the first one is */
+    renderer: *mut render::SDL_Renderer,
     sdl_textures: HashMap<TextureId, *mut SDL_Texture>,
+    texture_format: Option<pixels::SDL_PixelFormat>,
+    default_texture_filter: egui::TextureFilter,
+    next_user_texture: u64,
+    user_textures: HashMap<TextureId, UserTexture>,
+    user_texture_budget: Option<usize>,
+    draw_count: u64,
     draw_info: Option<DrawInfo>,
+    render_scale: Option<f32>,
+    offscreen_size: Option<(egui::Vec2, f32)>,
+    offscreen_pass: bool,
+    track_damage: bool,
+    last_frame: Option<DamageState>,
+    damage: Option<egui::Rect>,
+    background: Background,
+    opacity: f32,
+    color_space: ColorSpace,
+    fill_rect_fast_path: bool,
+    upload_budget: Option<usize>,
+    deferred_textures: Vec<(TextureId, egui::epaint::ImageDelta)>,
+    on_fatal_error: Option<FatalErrorHook>,
+    on_widget_event: Option<WidgetEventHook>,
+    focus_gained: Vec<egui::WidgetInfo>,
+    geometry_supported: Option<bool>,
+    render_failed: bool,
+    warned_missing_texture: bool,
+    frame_complete: bool,
+    frame_stats: FrameStats,
+    measure_timing: bool,
+    pass_started: Option<u64>,
+    pass_timing: Option<PassTiming>,
+}
+
+/* Options applied to the egui context before the first pass, so the first frame already uses
+ * them. Anything set here can still be changed later through `Painter::context()`. */
+pub struct PainterBuilder {
+    window: *mut SDL_Window,
+    visuals: Option<egui::Visuals>,
+    style: Option<egui::Style>,
+    scale_mode: ScaleMode,
+    on_fatal_error: Option<FatalErrorHook>,
+    raw_input: Option<egui::RawInput>,
+    clipboard: Box<dyn Clipboard>,
+    setup: Option<SetupHook>,
+}
+
+impl PainterBuilder {
+    pub fn new(window: *mut SDL_Window) -> Self {
+        Self {
+            window,
+            visuals: None,
+            style: None,
+            scale_mode: ScaleMode::default(),
+            on_fatal_error: None,
+            raw_input: None,
+            clipboard: Box::new(SdlClipboard),
+            setup: None,
+        }
+    }
+
+    pub fn with_visuals(mut self, visuals: egui::Visuals) -> Self {
+        self.visuals = Some(visuals);
+        self
+    }
+
+    /* Visuals set with `with_visuals` take precedence over the visuals in this style. */
+    pub fn with_style(mut self, style: egui::Style) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    pub fn with_scale_mode(mut self, scale_mode: ScaleMode) -> Self {
+        self.scale_mode = scale_mode;
+        self
+    }
+
+    /* Called with a description of SDL failures egui can't recover from, before the painter
+     * gives up on them. Errors are always logged, pass `show_error_message_box` to also tell
+     * the end user about them. */
+    pub fn with_on_fatal_error(mut self, on_fatal_error: impl FnMut(&str) + 'static) -> Self {
+        self.on_fatal_error = Some(Box::new(on_fatal_error));
+        self
+    }
+
+    /* See `Painter::with_raw_input` */
+    pub fn with_raw_input(mut self, raw_input: egui::RawInput) -> Self {
+        self.raw_input = Some(raw_input);
+        self
+    }
+
+    /* Replaces the SDL clipboard, see `Painter::set_clipboard` */
+    pub fn with_clipboard(mut self, clipboard: impl Clipboard + 'static) -> Self {
+        self.clipboard = Box::new(clipboard);
+        self
+    }
+
+    /* Called once with the new context, before any pass, to apply a theme's style, visuals or
+     * fonts in one place. Runs after `with_style` and `with_visuals`, so it has the last word. */
+    pub fn with_setup(mut self, setup: impl FnOnce(&egui::Context) + 'static) -> Self {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /* SAFETY: Same requirements as `Painter::new` */
+    pub fn build(self) -> Painter {
+        Painter::from_builder(self)
+    }
 }
 
 impl Painter {
     /* SAFETY: Painter must be intialized after SDL_Window has been created, otherwise getting
      * window size will fail. */
     pub fn new(window: *mut SDL_Window) -> Self {
-        let mut screen_size_x = 0;
-        let mut screen_size_y = 0;
-        unsafe { SDL_GetWindowSize(window, &mut screen_size_x, &mut screen_size_y) };
-        let mut screen_pixels_x = 0;
-        let mut screen_pixels_y = 0;
-        unsafe { SDL_GetWindowSizeInPixels(window, &mut screen_pixels_x, &mut screen_pixels_y) };
-        let pixels_per_point = screen_pixels_x as f32 / screen_pixels_x as f32;
+        PainterBuilder::new(window).build()
+    }
 
+    fn from_builder(builder: PainterBuilder) -> Self {
+        let window = builder.window;
         let looks = mouse::SDL_SYSTEM_CURSOR_DEFAULT;
-        let cursor = Cursor::new(looks).expect("Failed to init cursor");
+        // Some platforms, e.g. SDL's dummy and offscreen drivers, have no system cursors at all.
+        // The software cursor still works there.
+        let cursor = Cursor::new(looks)
+            .inspect_err(|e| log::warn!("Failed to init cursor: {}", e.to_string_lossy()))
+            .ok();
 
         let ctx = egui::Context::default();
-        ctx.set_pixels_per_point(pixels_per_point);
+        if let Some(style) = builder.style {
+            ctx.set_style(style);
+        }
+        if let Some(visuals) = builder.visuals {
+            ctx.set_visuals(visuals);
+        }
+        if let Some(setup) = builder.setup {
+            setup(&ctx);
+        }
 
-        Self {
+        let mut painter = Self {
             ctx,
-            cursor,
+            window,
+            cursors: HashMap::from([(looks, cursor)]),
+            cursor_looks: looks,
+            applied_cursor_icon: None,
+            cursor_icon: egui::CursorIcon::Default,
+            manage_cursor: true,
+            manage_text_input: true,
+            cursor_hidden: false,
+            software_cursor: false,
+            software_cursors: Vec::new(),
             cursor_pos: egui::Pos2 { x: 0.0, y: 0.0 },
+            relative_mouse_mode: RelativeMouseMode::default(),
+            pressed_buttons: Vec::new(),
+            double_click_radius: None,
+            force_line_scroll: false,
+            trackpad_pinch: true,
+            ctrl_is_command: true,
+            scroll_smoothing: None,
+            pending_scroll: egui::Vec2::ZERO,
+            input_enabled: true,
+            zoom_range: (0.2, 5.0),
+            mouse_device: None,
+            prefer_touch: true,
+            follow_system_theme: false,
+            text_input_active: false,
+            wants_text_input: false,
+            text_received: false,
+            text_consumed: false,
+            ime_composing: false,
+            repaint_forced: false,
+            needs_repaint: true,
+            repaint_after: std::time::Duration::ZERO,
+            last_drawn: Vec::new(),
+            last_pixels_per_point: 0.0,
+            pointer_finger: None,
+            locales: preferred_locales(),
+            safe_area: None,
+            scale_mode: builder.scale_mode,
+            pixel_density: 1.0,
+            content_scale: 1.0,
+            window_size: egui::Vec2::ZERO,
+            screen_size_pixels: egui::Vec2::ZERO,
             modifiers: egui::Modifiers::default(),
-            raw_input: egui::RawInput {
-                screen_rect: Some(egui::Rect::from_min_size(
-                    egui::Pos2::ZERO,
-                    egui::Vec2::new(screen_size_x as f32, screen_size_y as f32),
-                )),
-                ..Default::default()
-            },
+            raw_input: egui::RawInput::default(),
+            clipboard: builder.clipboard,
+            renderer: ptr::null_mut(),
             sdl_textures: Default::default(),
+            texture_format: None,
+            default_texture_filter: egui::TextureFilter::Linear,
+            next_user_texture: 0,
+            user_textures: HashMap::new(),
+            user_texture_budget: None,
+            draw_count: 0,
             draw_info: None,
+            render_scale: None,
+            offscreen_size: None,
+            offscreen_pass: false,
+            track_damage: false,
+            last_frame: None,
+            damage: None,
+            background: Background::default(),
+            opacity: 1.0,
+            color_space: ColorSpace::default(),
+            fill_rect_fast_path: false,
+            upload_budget: None,
+            deferred_textures: Vec::new(),
+            on_fatal_error: builder.on_fatal_error,
+            on_widget_event: None,
+            focus_gained: Vec::new(),
+            geometry_supported: None,
+            render_failed: false,
+            warned_missing_texture: false,
+            frame_complete: false,
+            frame_stats: FrameStats::default(),
+            measure_timing: false,
+            pass_started: None,
+            pass_timing: None,
+        };
+        match builder.raw_input {
+            Some(raw_input) => painter.seed_raw_input(raw_input),
+            None => {
+                painter.update_window_size(window);
+                painter.seed_cursor_pos();
+            }
+        }
+        // Already correct for the first pass, instead of egui's placeholder screen size
+        painter.raw_input.screen_rect = painter.input_screen_rect();
+        painter.update_safe_area();
+
+        // Follow SDL's own double click settings when the application has configured them
+        if let Some(ms) = hint_number(hints::SDL_HINT_MOUSE_DOUBLE_CLICK_TIME) {
+            painter.set_double_click_delay(ms / 1000.0);
         }
+        // egui has no radius for double clicks, it counts two clicks in a row as one. A press
+        // that moves further than the drag threshold is no click, which makes the threshold the
+        // closest match. The scale isn't final yet, so it is converted in `begin_pass`.
+        painter.double_click_radius =
+            hint_number(hints::SDL_HINT_MOUSE_DOUBLE_CLICK_RADIUS).map(|radius| radius as f32);
+        painter
     }
 
-    pub fn update_time(&mut self, duration: f64) {
-        self.raw_input.time = Some(duration);
+    /* Like `new`, but takes the screen size and pixels per point from `raw_input` instead of
+     * querying the window, which may not exist yet. The rest of `raw_input` is used as the
+     * input for the first pass. */
+    pub fn with_raw_input(window: *mut SDL_Window, raw_input: egui::RawInput) -> Self {
+        PainterBuilder::new(window)
+            .with_raw_input(raw_input)
+            .build()
     }
 
-    /* SAFETY: Unsafe interpretation of C union. Clipboard functions needs to be run from main
-     * thread. */
-    pub fn handle_event(&mut self, event: SDL_Event, window: *mut SDL_Window) -> bool {
-        let mut handled = false;
-        let event_type = unsafe { SDL_EventType(event.r#type) };
-        match event_type {
-            SDL_EventType::WINDOW_RESIZED | SDL_EventType::WINDOW_PIXEL_SIZE_CHANGED => {
-                let x = unsafe { event.window.data1 as f32 };
-                let y = unsafe { event.window.data2 as f32 };
-                self.raw_input.screen_rect = Some(egui::Rect::from_min_size(
-                    egui::Pos2::ZERO,
-                    egui::Vec2 { x, y },
-                ));
-            }
-            SDL_EventType::MOUSE_BUTTON_DOWN => {
-                // Click is set to handled only if it was made inside the area of the widget
-                // If egui is expecting keyboard input, clicking outside the area will cancel
-                // any input, but not mark the click as handled.
-                if self.ctx.is_pointer_over_area()
-                    || self.ctx.wants_pointer_input()
-                    || self.ctx.wants_keyboard_input()
-                {
-                    handled = self.ctx.is_pointer_over_area();
-                    let btn = unsafe {
-                        match event.button.button as i32 {
-                            mouse::SDL_BUTTON_LEFT => Some(egui::PointerButton::Primary),
-                            mouse::SDL_BUTTON_MIDDLE => Some(egui::PointerButton::Middle),
-                            mouse::SDL_BUTTON_RIGHT => Some(egui::PointerButton::Secondary),
-                            _ => None,
-                        }
-                    };
-                    if let Some(btn) = btn {
-                        self.raw_input.events.push(egui::Event::PointerButton {
-                            pos: self.cursor_pos,
-                            button: btn,
-                            pressed: true,
-                            modifiers: self.modifiers,
-                        });
-                    }
-                }
-            }
-            SDL_EventType::MOUSE_BUTTON_UP => {
-                if self.ctx.wants_pointer_input() {
-                    let btn = unsafe {
-                        match event.button.button as i32 {
-                            mouse::SDL_BUTTON_LEFT => Some(egui::PointerButton::Primary),
-                            mouse::SDL_BUTTON_MIDDLE => Some(egui::PointerButton::Middle),
-                            mouse::SDL_BUTTON_RIGHT => Some(egui::PointerButton::Secondary),
-                            _ => None,
-                        }
-                    };
-                    if let Some(btn) = btn {
-                        self.raw_input.events.push(egui::Event::PointerButton {
-                            pos: self.cursor_pos,
-                            button: btn,
-                            pressed: false,
-                            modifiers: self.modifiers,
-                        });
-                    }
-                    handled = true;
-                }
-            }
-            SDL_EventType::MOUSE_MOTION => {
-                let x = unsafe { event.motion.x as f32 };
-                let y = unsafe { event.motion.y as f32 };
-                let screen_rect = self.ctx.screen_rect();
-                self.cursor_pos.x = x.clamp(screen_rect.min.x, screen_rect.max.x - 1.0);
-                self.cursor_pos.y = y.clamp(screen_rect.min.y, screen_rect.max.y - 1.0);
-                self.raw_input
-                    .events
-                    .push(egui::Event::PointerMoved(self.cursor_pos));
-            }
-            SDL_EventType::MOUSE_WHEEL => {
-                if self.ctx.wants_pointer_input() {
-                    let x = unsafe { event.wheel.x as f32 };
-                    let y = unsafe { event.wheel.y as f32 };
-                    let delta = egui::Vec2::new(x, y);
-                    let mod_state = unsafe { SDL_GetModState() };
-                    let left_ctrl = mod_state & keycode::SDL_KMOD_LCTRL > 0;
-                    let right_ctrl = mod_state & keycode::SDL_KMOD_RCTRL > 0;
+    fn seed_raw_input(&mut self, mut raw_input: egui::RawInput) {
+        let native_pixels_per_point = raw_input.viewport().native_pixels_per_point.unwrap_or(1.0);
+        let screen_rect = raw_input.screen_rect.take();
+        self.raw_input = raw_input;
+        self.modifiers = self.raw_input.modifiers;
+        if let Some(screen_rect) = screen_rect {
+            self.window_size = screen_rect.size();
+            self.set_pixel_size(screen_rect.size() * native_pixels_per_point);
+        }
+    }
 
-                    if left_ctrl || right_ctrl {
-                        self.raw_input
-                            .events
-                            .push(egui::Event::Zoom((delta.y / 125.0).exp()));
-                    }
-                    handled = true;
-                }
-            }
-            SDL_EventType::KEY_DOWN => {
-                if self.ctx.wants_keyboard_input() {
-                    let keycode = unsafe { event.key.key };
-                    if keycode != keycode::SDLK_UNKNOWN {
-                        if let Some(key) = sdl_key_to_egui(keycode) {
-                            self.modifiers = get_modifiers();
-                            self.raw_input.modifiers = self.modifiers;
-
-                            if self.modifiers.command {
-                                match key {
-                                    egui::Key::C => self.raw_input.events.push(egui::Event::Copy),
-                                    egui::Key::X => self.raw_input.events.push(egui::Event::Cut),
-                                    egui::Key::V => unsafe {
-                                        if clipboard::SDL_HasClipboardText() {
-                                            let text = clipboard::SDL_GetClipboardText();
-                                            if !text.is_null() {
-                                                if let Ok(text) = CStr::from_ptr(text).to_str() {
-                                                    self.raw_input
-                                                        .events
-                                                        .push(egui::Event::Text(text.to_string()));
-                                                }
-                                                SDL_free(text as *mut _);
-                                            }
-                                        }
-                                    },
-                                    _ => {}
-                                }
-                            }
+    /* SAFETY: This needs to be called from main thread */
+    fn update_window_size(&mut self, window: *mut SDL_Window) {
+        let mut size_x = 0;
+        let mut size_y = 0;
+        unsafe { SDL_GetWindowSize(window, &mut size_x, &mut size_y) };
+        let mut pixels_x = 0;
+        let mut pixels_y = 0;
+        unsafe { SDL_GetWindowSizeInPixels(window, &mut pixels_x, &mut pixels_y) };
 
-                            unsafe { SDL_StartTextInput(window) };
-                            self.raw_input.focused = true;
-                            self.raw_input.events.push(egui::Event::Key {
-                                key,
-                                physical_key: Some(key),
-                                pressed: true,
-                                repeat: false,
-                                modifiers: self.modifiers,
-                            });
-                            handled = true;
-                        }
-                    }
-                }
-            }
-            SDL_EventType::KEY_UP => {
-                if self.ctx.wants_keyboard_input() {
-                    let keycode = unsafe { event.key.key };
-
-                    match keycode {
-                        keycode::SDLK_UNKNOWN => {}
-                        keycode::SDLK_ESCAPE => unsafe {
-                            SDL_StopTextInput(window);
-                        },
-                        _ => {
-                            if let Some(key) = sdl_key_to_egui(keycode) {
-                                self.modifiers = get_modifiers();
-                                self.raw_input.modifiers = self.modifiers;
-
-                                self.raw_input.events.push(egui::Event::Key {
-                                    key,
-                                    physical_key: Some(key),
-                                    pressed: false,
-                                    repeat: false,
-                                    modifiers: self.modifiers,
-                                });
-                                handled = true;
-                            }
-                        }
-                    }
-                }
-            }
-            SDL_EventType::TEXT_INPUT => unsafe {
-                if self.ctx.wants_keyboard_input() {
-                    self.modifiers = get_modifiers();
-                    self.raw_input.modifiers = self.modifiers;
-                    let text = event.text.text;
-                    let text = CStr::from_ptr(text);
-                    if let Ok(text) = text.to_str() {
-                        self.raw_input
-                            .events
-                            .push(egui::Event::Text(text.to_string()));
-                        handled = true;
-                    }
-                }
-            },
-            _ => {}
+        self.window_size = egui::Vec2::new(size_x as f32, size_y as f32);
+        let mut pixels = egui::Vec2::new(pixels_x as f32, pixels_y as f32);
+        if pixels.x <= 0.0 || pixels.y <= 0.0 {
+            // Not every backend knows the pixel size before the window is shown
+            pixels = self.window_size * unsafe { video::SDL_GetWindowPixelDensity(window) };
         }
-
-        handled
+        self.set_pixel_size(pixels);
+        self.update_content_scale(window);
     }
 
-    pub fn begin_pass(&mut self) -> egui::Context {
+    /* SAFETY: This needs to be called from main thread */
+    fn update_content_scale(&mut self, window: *mut SDL_Window) {
+        let display_scale = unsafe { video::SDL_GetWindowDisplayScale(window) };
+        let pixel_density = unsafe { video::SDL_GetWindowPixelDensity(window) };
+        // Both are 0 on failure
+        if display_scale > 0.0 && pixel_density > 0.0 {
+            self.content_scale = display_scale / pixel_density;
+            self.update_native_pixels_per_point();
+        }
+    }
+
+    fn update_native_pixels_per_point(&mut self) {
+        let native_pixels_per_point = self.native_pixels_per_point();
+        self.raw_input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(native_pixels_per_point);
+    }
+
+    fn native_pixels_per_point(&self) -> f32 {
+        self.scale_mode
+            .apply(self.pixel_density * self.content_scale)
+    }
+
+    fn set_pixel_size(&mut self, pixels: egui::Vec2) {
+        // Some window managers shrink the window to nothing while minimizing, keep the last
+        // real size rather than laying out egui in an empty rect
+        if pixels.x <= 0.0 || pixels.y <= 0.0 {
+            return;
+        }
+        if self.window_size.x > 0.0 {
+            self.pixel_density = pixels.x / self.window_size.x;
+        }
+        self.screen_size_pixels = pixels;
+        self.update_native_pixels_per_point();
+    }
+
+    /* The scale egui will use for the next pass, known before the pass has started. */
+    fn input_pixels_per_point(&self) -> f32 {
+        self.native_pixels_per_point() * self.ctx.zoom_factor()
+    }
+
+    /* Without this hover state is wrong until the first motion event if the cursor starts out
+     * above a widget.
+     * SAFETY: This needs to be called from main thread */
+    fn seed_cursor_pos(&mut self) {
+        if unsafe { mouse::SDL_GetMouseFocus() } != self.window {
+            return;
+        }
+        let mut x = 0.0;
+        let mut y = 0.0;
+        unsafe { mouse::SDL_GetMouseState(&mut x, &mut y) };
+        let Some(screen_rect) = self.input_screen_rect() else {
+            return;
+        };
+        let pos = self.window_to_points(x, y);
+        self.cursor_pos.x = pos.x.clamp(screen_rect.min.x, screen_rect.max.x - 1.0);
+        self.cursor_pos.y = pos.y.clamp(screen_rect.min.y, screen_rect.max.y - 1.0);
+        self.raw_input
+            .events
+            .push(egui::Event::PointerMoved(self.cursor_pos));
+    }
+
+    /* egui sizes and positions windows in points, SDL in window coordinates. */
+    fn points_to_window(&self, points: egui::Vec2) -> egui::Vec2 {
+        points * (self.input_pixels_per_point() / self.pixel_density)
+    }
+
+    /* The part of the screen in points that isn't covered by notches, rounded display corners
+     * or system bars, e.g. to use as the margin of a central panel. The whole screen on
+     * platforms without such areas. */
+    pub fn safe_area(&self) -> egui::Rect {
+        let screen_rect = self.screen_rect_points();
+        let Some(area) = self.safe_area else {
+            return screen_rect;
+        };
+        let min = self.window_to_points(area.x as f32, area.y as f32);
+        let max = self.window_to_points((area.x + area.w) as f32, (area.y + area.h) as f32);
+        egui::Rect::from_min_max(min, max).intersect(screen_rect)
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn update_safe_area(&mut self) {
+        let mut area = SDL_Rect::default();
+        let known = unsafe { video::SDL_GetWindowSafeArea(self.window, &mut area) };
+        self.safe_area = (known && area.w > 0 && area.h > 0).then_some(area);
+    }
+
+    /* Converts SDL window coordinates, as used by mouse events, to egui points. */
+    fn window_to_points(&self, x: f32, y: f32) -> egui::Pos2 {
+        egui::Pos2::new(x, y) * (self.pixel_density / self.input_pixels_per_point())
+    }
+
+    /* Conversions between egui points and the renderer pixels `draw` renders egui in, for
+     * drawing SDL content lined up with egui widgets. */
+    #[inline]
+    pub fn points_to_pixels(&self, pos: egui::Pos2) -> (f32, f32) {
+        let pos = pos * self.ctx.pixels_per_point();
+        (pos.x, pos.y)
+    }
+
+    #[inline]
+    pub fn pixels_to_points(&self, x: f32, y: f32) -> egui::Pos2 {
+        egui::Pos2::new(x, y) / self.ctx.pixels_per_point()
+    }
+
+    #[inline]
+    pub fn rect_to_pixels(&self, rect: egui::Rect) -> SDL_FRect {
+        let rect = rect * self.ctx.pixels_per_point();
+        SDL_FRect {
+            x: rect.min.x,
+            y: rect.min.y,
+            w: rect.width(),
+            h: rect.height(),
+        }
+    }
+
+    #[inline]
+    pub fn rect_from_pixels(&self, rect: SDL_FRect) -> egui::Rect {
+        let min = self.pixels_to_points(rect.x, rect.y);
+        egui::Rect::from_min_size(
+            min,
+            egui::vec2(rect.w, rect.h) / self.ctx.pixels_per_point(),
+        )
+    }
+
+    pub fn set_on_fatal_error(&mut self, on_fatal_error: impl FnMut(&str) + 'static) {
+        self.on_fatal_error = Some(Box::new(on_fatal_error));
+    }
+
+    fn report_fatal_error(&mut self, message: &str) {
+        log::error!("{message}");
+        if let Some(on_fatal_error) = &mut self.on_fatal_error {
+            on_fatal_error(message);
+        }
+    }
+
+    pub fn context(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    /* Whether the window the painter was created with still exists. Keeping the window and
+     * renderer alive while the painter uses them is up to the caller, this is only meant for
+     * checks during teardown.
+     * SAFETY: This needs to be called from main thread */
+    pub fn is_valid(&self) -> bool {
+        // SDL validates window pointers, so this is fine for destroyed windows too
+        !self.window.is_null() && unsafe { video::SDL_GetWindowID(self.window) } != 0
+    }
+
+    /* Copy and paste in egui go through `clipboard` instead of SDL from now on. */
+    pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+        self.clipboard = Box::new(clipboard);
+    }
+
+    fn clipboard_text(&mut self) -> Option<String> {
+        // egui expects plain newlines, Windows clipboards use CRLF and old macOS text CR
+        let text = self.clipboard.get_text()?;
+        Some(text.replace("\r\n", "\n").replace('\r', "\n"))
+    }
+
+    /* Called from `end_pass` for every widget interaction egui reports (clicks, focus changes,
+     * value changes), e.g. to play a sound or rumble a gamepad. */
+    pub fn set_on_widget_event(
+        &mut self,
+        on_widget_event: impl FnMut(&egui::output::OutputEvent) + 'static,
+    ) {
+        self.on_widget_event = Some(Box::new(on_widget_event));
+    }
+
+    pub fn clear_on_widget_event(&mut self) {
+        self.on_widget_event = None;
+    }
+
+    /* The widgets that gained keyboard focus during the last pass. */
+    pub fn focus_gained(&self) -> &[egui::WidgetInfo] {
+        &self.focus_gained
+    }
+
+    pub fn focused_widget(&self) -> Option<egui::Id> {
+        self.ctx.memory(|memory| memory.focused())
+    }
+
+    /* Only let the given mouse drive egui, or any mouse when None. */
+    pub fn set_mouse_device(&mut self, device: Option<SDL_MouseID>) {
+        self.mouse_device = device;
+    }
+
+    /* When true (default) the first finger drives the egui pointer and the mouse events SDL
+     * synthesizes from touch are ignored. When false the synthesized mouse events are used
+     * instead and fingers only feed egui's multi-touch gestures. */
+    pub fn set_prefer_touch(&mut self, prefer_touch: bool) {
+        self.prefer_touch = prefer_touch;
+        self.pointer_finger = None;
+    }
+
+    pub fn set_relative_mouse_mode(&mut self, mode: RelativeMouseMode) {
+        self.relative_mouse_mode = mode;
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn is_relative_mouse_mode(&self) -> bool {
+        unsafe { mouse::SDL_GetWindowRelativeMouseMode(self.window) }
+    }
+
+    fn accepts_mouse(&self, which: SDL_MouseID) -> bool {
+        if self.relative_mouse_mode == RelativeMouseMode::Suspend && self.is_relative_mouse_mode() {
+            return false;
+        }
+        if which == SDL_TOUCH_MOUSEID {
+            return !self.prefer_touch;
+        }
+        self.mouse_device.is_none_or(|id| id == which)
+    }
+
+    /* Drops input queued for the next pass, e.g. after a scene transition. egui's own widget
+     * state such as focus or scroll positions is left untouched. */
+    pub fn reset_input(&mut self) {
+        self.raw_input.events.clear();
+        self.pointer_finger = None;
+        self.modifiers = egui::Modifiers::default();
+        self.raw_input.modifiers = self.modifiers;
+        // Release buttons egui saw pressed, their release events may never reach it otherwise
+        for button in self.pressed_buttons.drain(..) {
+            self.raw_input.events.push(egui::Event::PointerButton {
+                pos: self.cursor_pos,
+                button,
+                pressed: false,
+                modifiers: self.modifiers,
+            });
+        }
+        self.raw_input.events.push(egui::Event::PointerGone);
+    }
+
+    /* Always scroll in lines, even for the fractional deltas of precise touchpads. */
+    pub fn set_force_line_scroll(&mut self, force: bool) {
+        self.force_line_scroll = force;
+    }
+
+    /* Whether Ctrl triggers egui's command shortcuts such as copy, undo or zoom, on by default.
+     * Games that bind Ctrl themselves can turn this off, egui still sees Ctrl as `ctrl` but no
+     * longer as `command`. Ignored on macOS, where Cmd is the command key and Ctrl never is. */
+    pub fn set_treat_ctrl_as_command(&mut self, ctrl_is_command: bool) {
+        self.ctrl_is_command = ctrl_is_command;
+    }
+
+    /* Whether pinching on a touchpad zooms egui, on by default. Ctrl with a notched wheel
+     * always does. */
+    pub fn set_trackpad_pinch(&mut self, enabled: bool) {
+        self.trackpad_pinch = enabled;
+    }
+
+    /* Collects the many small deltas of precise touchpads into one scroll per pass. Each pass
+     * scrolls by `factor` of the distance collected so far, so 1.0 only merges the deltas of a
+     * frame and smaller factors spread them over the next frames as well. None, the default,
+     * hands every delta to egui as it arrives. Notched wheels are never smoothed. */
+    pub fn set_scroll_smoothing(&mut self, factor: Option<f32>) {
+        self.scroll_smoothing = factor.map(|factor| factor.clamp(0.01, 1.0));
+        if self.scroll_smoothing.is_none() {
+            self.flush_scroll(1.0);
+        }
+    }
+
+    fn flush_scroll(&mut self, factor: f32) {
+        if self.pending_scroll == egui::Vec2::ZERO {
+            return;
+        }
+        // The rest of a smoothed scroll is dropped in once it is too small to see
+        let mut delta = self.pending_scroll * factor;
+        if (self.pending_scroll - delta).length() < 0.5 {
+            delta = self.pending_scroll;
+        }
+        self.pending_scroll -= delta;
+        self.raw_input.events.push(egui::Event::MouseWheel {
+            unit: egui::MouseWheelUnit::Point,
+            delta,
+            modifiers: self.modifiers,
+        });
+        if self.pending_scroll != egui::Vec2::ZERO {
+            self.ctx.request_repaint();
+        }
+    }
+
+    /* While disabled, `handle_event` passes every event through to the application and egui
+     * renders without reacting to input, e.g. during a cutscene. */
+    pub fn set_input_enabled(&mut self, enabled: bool) {
+        if enabled == self.input_enabled {
+            return;
+        }
+        self.input_enabled = enabled;
+        self.reset_input();
+    }
+
+    /* Seconds between two presses for them to count as a double click. */
+    pub fn set_double_click_delay(&mut self, seconds: f64) {
+        self.ctx
+            .options_mut(|o| o.input_options.max_double_click_delay = seconds);
+    }
+
+    /* Distance in points the pointer can move while pressed before a click becomes a drag.
+     * Replaces the one taken from SDL_HINT_MOUSE_DOUBLE_CLICK_RADIUS. */
+    pub fn set_drag_threshold(&mut self, points: f32) {
+        self.double_click_radius = None;
+        self.ctx
+            .options_mut(|o| o.input_options.max_click_dist = points);
+    }
+
+    /* Seconds egui takes to animate e.g. collapsing headers, in both the light and dark style.
+     * Can be called before the first pass. */
+    pub fn set_animation_time(&mut self, seconds: f32) {
+        self.ctx
+            .all_styles_mut(|style| style.animation_time = seconds.max(0.0));
+    }
+
+    /* For users who prefer reduced motion, or tools that should feel instant. */
+    pub fn disable_animations(&mut self) {
+        self.set_animation_time(0.0);
+    }
+
+    /* Seconds the pointer has to rest on a widget before its tooltip shows. */
+    pub fn set_tooltip_delay(&mut self, seconds: f32) {
+        self.ctx
+            .all_styles_mut(|style| style.interaction.tooltip_delay = seconds.max(0.0));
+    }
+
+    /* Let egui pick light or dark visuals from the desktop theme, tracking changes at runtime.
+     * SDL has no notion of the desktop accent color, so selection colors stay egui's own. */
+    pub fn set_follow_system_theme(&mut self, follow: bool) {
+        self.follow_system_theme = follow;
+        self.update_system_theme();
+    }
+
+    fn update_system_theme(&mut self) {
+        self.raw_input.system_theme = if self.follow_system_theme {
+            match unsafe { SDL_GetSystemTheme() } {
+                SDL_SystemTheme::LIGHT => Some(egui::Theme::Light),
+                SDL_SystemTheme::DARK => Some(egui::Theme::Dark),
+                _ => None,
+            }
+        } else {
+            None
+        };
+    }
+
+    /* When false, `end_pass` leaves the OS cursor alone. The icon egui asks for is still
+     * available from `cursor_icon()` to apply manually. */
+    pub fn set_manage_cursor(&mut self, manage: bool) {
+        self.manage_cursor = manage;
+        // The application may have set its own cursor in the meantime
+        self.applied_cursor_icon = None;
+    }
+
+    /* Confines the cursor to the window, e.g. for drags that would otherwise run off its edge.
+     * Released again when the window loses focus.
+     * SAFETY: This needs to be called from main thread */
+    pub fn set_cursor_grab(&mut self, grab: bool) {
+        if unsafe { !video::SDL_SetWindowMouseGrab(self.window, grab) } {
+            log::warn!("Failed to set mouse grab: {}", sdl_error());
+        }
+    }
+
+    /* While disabled, `end_pass` leaves SDL text input alone, for applications that start and
+     * stop it for their own text fields. egui only receives typed text while text input is
+     * active, so the application should start it whenever `wants_text_input` is true. */
+    pub fn set_manage_text_input(&mut self, manage: bool) {
+        self.manage_text_input = manage;
+        // Picks up whatever state the application left text input in
+        self.text_input_active = unsafe { SDL_TextInputActive(self.window) };
+    }
+
+    /* Draw the cursor with `draw` instead of showing the OS cursor, e.g. when streaming the
+     * window. `draw` renders it whenever this is enabled, `end_pass` also hides the OS cursor
+     * unless `set_manage_cursor(false)` left that to the application. */
+    pub fn set_software_cursor(&mut self, enabled: bool) {
+        self.software_cursor = enabled;
+    }
+
+    /* Image for the software cursor when egui asks for `icon`, the hotspot is in pixels of the
+     * image. Icons without their own image use the one for `CursorIcon::Default`, which is a
+     * plain arrow unless replaced.
+     * SAFETY: This needs to be called from main thread */
+    pub fn set_software_cursor_image(
+        &mut self,
+        icon: egui::CursorIcon,
+        image: egui::ColorImage,
+        hotspot: egui::Vec2,
+    ) {
+        self.software_cursors.retain(|cursor| {
+            if cursor.icon != icon {
+                return true;
+            }
+            if !cursor.texture.is_null() {
+                unsafe { SDL_DestroyTexture(cursor.texture) };
+            }
+            false
+        });
+        self.software_cursors.push(SoftwareCursor {
+            icon,
+            image,
+            hotspot,
+            texture: ptr::null_mut(),
+        });
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn draw_software_cursor(&mut self, renderer: *mut render::SDL_Renderer, pixels_per_point: f32) {
+        let Some(pos) = self.ctx.input(|i| i.pointer.hover_pos()) else {
+            return;
+        };
+        if self.cursor_icon == egui::CursorIcon::None {
+            return;
+        }
+        let texture_format = self.texture_format(renderer);
+        let find = |cursors: &[SoftwareCursor], icon| cursors.iter().position(|c| c.icon == icon);
+        if find(&self.software_cursors, egui::CursorIcon::Default).is_none() {
+            self.software_cursors.push(SoftwareCursor::arrow());
+        }
+        let index = find(&self.software_cursors, self.cursor_icon)
+            .or_else(|| find(&self.software_cursors, egui::CursorIcon::Default))
+            .expect("The default cursor was added above");
+        let cursor = &mut self.software_cursors[index];
+
+        unsafe {
+            if cursor.texture.is_null() {
+                cursor.texture = create_texture(renderer, texture_format, cursor.image.size);
+                if cursor.texture.is_null() {
+                    log::error!("Failed to create software cursor: {}", sdl_error());
+                    return;
+                }
+                let sdl_pixels = texture_pixels(&cursor.image, texture_format);
+                SDL_UpdateTexture(
+                    cursor.texture,
+                    ptr::null(),
+                    sdl_pixels.as_ptr() as *const std::ffi::c_void,
+                    (cursor.image.width() * 4) as i32,
+                );
+                set_texture_filter(cursor.texture, egui::TextureFilter::Nearest);
+            }
+
+            // Scaled like the rest of the UI so the cursor keeps its size relative to it
+            let min = pos.to_vec2() * pixels_per_point - cursor.hotspot * pixels_per_point;
+            let dst = SDL_FRect {
+                x: min.x,
+                y: min.y,
+                w: cursor.image.width() as f32 * pixels_per_point,
+                h: cursor.image.height() as f32 * pixels_per_point,
+            };
+            render::SDL_SetRenderClipRect(renderer, ptr::null());
+            render::SDL_RenderTexture(renderer, cursor.texture, ptr::null(), &dst);
+        }
+    }
+
+    /* The cursor icon requested by egui in the last pass. */
+    pub fn cursor_icon(&self) -> egui::CursorIcon {
+        self.cursor_icon
+    }
+
+    /* Current Caps Lock and Num Lock state, e.g. for a status bar. */
+    pub fn lock_state(&self) -> LockState {
+        get_lock_state()
+    }
+
+    /* The user's preferred locales as reported by SDL, most preferred first. Updated when
+     * `handle_event` receives `SDL_EVENT_LOCALE_CHANGED`. egui itself always lays out left to
+     * right and doesn't reorder bidirectional text, so this is for the application to pick
+     * translations or layouts. */
+    pub fn locales(&self) -> &[Locale] {
+        &self.locales
+    }
+
+    pub fn update_time(&mut self, duration: f64) {
+        self.raw_input.time = Some(duration);
+    }
+
+    /* Advances the time by the duration of the rendered frame in seconds and begins the pass for
+     * it. Meant for loops with a fixed simulation step, where egui should only follow the frames
+     * actually rendered and not the simulation ticks. */
+    pub fn step(&mut self, render_dt: f32) -> egui::Context {
+        let time = self.raw_input.time.unwrap_or(0.0) + render_dt as f64;
+        self.raw_input.time = Some(time);
+        self.raw_input.predicted_dt = render_dt;
+        self.begin_pass()
+    }
+
+    /* Returns true if egui consumed the event. The window argument is ignored, the painter
+     * always uses the window it was created with. It is only kept so existing callers still
+     * compile, passing `ptr::null_mut()` is fine.
+     * SAFETY: Unsafe interpretation of C union. Clipboard functions needs to be run from main
+     * thread. */
+    pub fn handle_event(&mut self, event: SDL_Event, _window: *mut SDL_Window) -> bool {
+        debug_assert!(!self.window.is_null(), "handle_event without a window");
+        if self.window.is_null() {
+            return false;
+        }
+        let mut handled = false;
+        let event_type = unsafe { SDL_EventType(event.r#type) };
+        // Window state is still tracked so the frozen UI keeps rendering correctly
+        let window_state = matches!(
+            event_type,
+            SDL_EventType::WINDOW_RESIZED
+                | SDL_EventType::WINDOW_PIXEL_SIZE_CHANGED
+                | SDL_EventType::WINDOW_DISPLAY_SCALE_CHANGED
+                | SDL_EventType::WINDOW_EXPOSED
+                | SDL_EventType::WINDOW_FOCUS_LOST
+                | SDL_EventType::RENDER_DEVICE_RESET
+                | SDL_EventType::SYSTEM_THEME_CHANGED
+                | SDL_EventType::LOCALE_CHANGED
+                | SDL_EventType::WINDOW_SAFE_AREA_CHANGED
+        );
+        if !self.input_enabled && !window_state {
+            return false;
+        }
+        let mouse_id = unsafe {
+            match event_type {
+                SDL_EventType::MOUSE_MOTION => Some(event.motion.which),
+                SDL_EventType::MOUSE_BUTTON_DOWN | SDL_EventType::MOUSE_BUTTON_UP => {
+                    Some(event.button.which)
+                }
+                SDL_EventType::MOUSE_WHEEL => Some(event.wheel.which),
+                _ => None,
+            }
+        };
+        if mouse_id.is_some_and(|id| !self.accepts_mouse(id)) {
+            return false;
+        }
+
+        match event_type {
+            SDL_EventType::WINDOW_RESIZED => {
+                // Logical size, the matching pixel size arrives in WINDOW_PIXEL_SIZE_CHANGED
+                let x = unsafe { event.window.data1 as f32 };
+                let y = unsafe { event.window.data2 as f32 };
+                if x > 0.0 && y > 0.0 {
+                    self.window_size = egui::Vec2 { x, y };
+                    self.screen_size_pixels = self.window_size * self.pixel_density;
+                }
+            }
+            SDL_EventType::WINDOW_PIXEL_SIZE_CHANGED => {
+                let x = unsafe { event.window.data1 as f32 };
+                let y = unsafe { event.window.data2 as f32 };
+                self.set_pixel_size(egui::Vec2 { x, y });
+            }
+            SDL_EventType::MOUSE_BUTTON_DOWN => {
+                // egui acts on a click over one of its areas, while it is dragging, and to take
+                // keyboard focus away from a text field. Only then is the click handed to egui
+                // and reported as handled, anything else is left to the application.
+                let btn = sdl_button_to_egui(unsafe { event.button.button }).filter(|_| {
+                    self.ctx.is_pointer_over_area()
+                        || self.ctx.wants_pointer_input()
+                        || self.ctx.wants_keyboard_input()
+                });
+                if let Some(btn) = btn {
+                    self.pressed_buttons.push(btn);
+                    let pos = self.button_pos(unsafe { &event.button });
+                    self.raw_input.events.push(egui::Event::PointerButton {
+                        pos,
+                        button: btn,
+                        pressed: true,
+                        modifiers: self.modifiers,
+                    });
+                    handled = true;
+                }
+            }
+            SDL_EventType::MOUSE_BUTTON_UP => {
+                // A release always goes where the press went, so neither egui nor the
+                // application is left with a button held down
+                let btn = sdl_button_to_egui(unsafe { event.button.button }).filter(|btn| {
+                    self.pressed_buttons.contains(btn) || self.ctx.wants_pointer_input()
+                });
+                if let Some(btn) = btn {
+                    self.pressed_buttons.retain(|&pressed| pressed != btn);
+                    let pos = self.button_pos(unsafe { &event.button });
+                    self.raw_input.events.push(egui::Event::PointerButton {
+                        pos,
+                        button: btn,
+                        pressed: false,
+                        modifiers: self.modifiers,
+                    });
+                    handled = true;
+                }
+            }
+            SDL_EventType::MOUSE_MOTION => {
+                // Unlike the position, relative motion keeps going at the window edges, which
+                // egui uses for e.g. DragValue
+                let xrel = unsafe { event.motion.xrel };
+                let yrel = unsafe { event.motion.yrel };
+                let delta = self.window_to_points(xrel, yrel).to_vec2();
+                self.raw_input.events.push(egui::Event::MouseMoved(delta));
+
+                let pos = if self.is_relative_mouse_mode() {
+                    // The absolute position is pinned in relative mode
+                    self.cursor_pos + delta
+                } else {
+                    let x = unsafe { event.motion.x };
+                    let y = unsafe { event.motion.y };
+                    self.window_to_points(x, y)
+                };
+                self.set_cursor_pos(pos);
+                self.raw_input
+                    .events
+                    .push(egui::Event::PointerMoved(self.cursor_pos));
+            }
+            SDL_EventType::FINGER_DOWN => {
+                handled = self.handle_finger(unsafe { &event.tfinger }, egui::TouchPhase::Start);
+            }
+            SDL_EventType::FINGER_MOTION => {
+                handled = self.handle_finger(unsafe { &event.tfinger }, egui::TouchPhase::Move);
+            }
+            SDL_EventType::FINGER_UP => {
+                handled = self.handle_finger(unsafe { &event.tfinger }, egui::TouchPhase::End);
+            }
+            SDL_EventType::FINGER_CANCELED => {
+                handled = self.handle_finger(unsafe { &event.tfinger }, egui::TouchPhase::Cancel);
+            }
+            SDL_EventType::MOUSE_WHEEL if self.ctx.wants_pointer_input() => {
+                // Scroll areas are picked by the pointer position, which can be ahead of the
+                // last motion event
+                if !self.is_relative_mouse_mode() {
+                    let pos =
+                        unsafe { self.window_to_points(event.wheel.mouse_x, event.wheel.mouse_y) };
+                    let last_pos = self.cursor_pos;
+                    self.set_cursor_pos(pos);
+                    if self.cursor_pos != last_pos {
+                        self.raw_input
+                            .events
+                            .push(egui::Event::PointerMoved(self.cursor_pos));
+                    }
+                }
+                let x = unsafe { event.wheel.x };
+                let y = unsafe { event.wheel.y };
+                // SDL reports where the view moves while egui wants where the content moves
+                let mut delta = egui::Vec2::new(-x, y);
+                if unsafe { event.wheel.direction } == mouse::SDL_MOUSEWHEEL_FLIPPED {
+                    delta = -delta;
+                }
+                // Zooming follows egui's command modifier, so it stays out of the way of
+                // applications that keep Ctrl for themselves with `ctrl_is_command`
+                let modifiers = get_modifiers(self.ctrl_is_command);
+
+                // Notched wheels move in whole steps, precise touchpads and wheels report
+                // fractions which scroll the exact distance without smoothing
+                let precise = x.fract() != 0.0 || y.fract() != 0.0;
+                if modifiers.command && precise {
+                    // Touchpad drivers report pinches as Ctrl+wheel, in much finer steps
+                    // than a wheel
+                    if self.trackpad_pinch {
+                        let line = self.ctx.options(|o| o.input_options.line_scroll_speed);
+                        self.raw_input
+                            .events
+                            .push(egui::Event::Zoom((delta.y * line / 200.0).exp()));
+                    }
+                } else if modifiers.command {
+                    self.raw_input
+                        .events
+                        .push(egui::Event::Zoom((delta.y / 125.0).exp()));
+                } else {
+                    let (unit, delta) = if precise && !self.force_line_scroll {
+                        let line = self.ctx.options(|o| o.input_options.line_scroll_speed);
+                        (egui::MouseWheelUnit::Point, delta * line)
+                    } else {
+                        (egui::MouseWheelUnit::Line, delta)
+                    };
+                    if unit == egui::MouseWheelUnit::Point && self.scroll_smoothing.is_some() {
+                        self.pending_scroll += delta;
+                    } else {
+                        self.raw_input.events.push(egui::Event::MouseWheel {
+                            unit,
+                            delta,
+                            modifiers,
+                        });
+                    }
+                }
+                handled = true;
+            }
+            SDL_EventType::KEY_DOWN => {
+                // Modifier presses count even without focus, so the first key of a chord isn't
+                // lost by the time focus arrives
+                let r#mod = unsafe { event.key.r#mod };
+                self.update_key_modifiers(r#mod);
+                // The event's own Num Lock state, as the global one may already be ahead of it
+                let num_lock = r#mod & keycode::SDL_KMOD_NUM > 0;
+                let keycode = keypad_key(unsafe { event.key.key }, num_lock);
+                let wants_keyboard = self.ctx.wants_keyboard_input();
+                // egui zooms with command +/-/0 without needing keyboard focus, and navigation
+                // keys are how keyboard users move focus into the UI in the first place
+                if keycode != keycode::SDLK_UNKNOWN
+                    && let Some(key) = sdl_key_to_egui(keycode)
+                    && (wants_keyboard || self.is_zoom_shortcut(keycode) || is_navigation_key(key))
+                {
+                    self.raw_input.focused = true;
+                    // Unless egui has focus, the application still gets its arrow keys
+                    handled = wants_keyboard || !is_navigation_key(key);
+
+                    // Clipboard shortcuts become egui's clipboard events instead of key
+                    // presses, other combinations such as select all or undo reach egui
+                    // as key presses
+                    if let Some(shortcut) = clipboard_shortcut(key, self.modifiers) {
+                        match shortcut {
+                            egui::Key::C => self.raw_input.events.push(egui::Event::Copy),
+                            egui::Key::X => self.raw_input.events.push(egui::Event::Cut),
+                            _ => {
+                                if let Some(text) = self.clipboard_text() {
+                                    self.raw_input.events.push(egui::Event::Paste(text));
+                                    self.text_received = true;
+                                }
+                            }
+                        }
+                    } else {
+                        self.raw_input.events.push(egui::Event::Key {
+                            key,
+                            physical_key: Some(key),
+                            pressed: true,
+                            repeat: false,
+                            modifiers: self.modifiers,
+                        });
+                    }
+                }
+            }
+            SDL_EventType::KEY_UP => {
+                let r#mod = unsafe { event.key.r#mod };
+                self.update_key_modifiers(r#mod);
+                let num_lock = r#mod & keycode::SDL_KMOD_NUM > 0;
+                let keycode = keypad_key(unsafe { event.key.key }, num_lock);
+                let wants_keyboard = self.ctx.wants_keyboard_input();
+                if keycode != keycode::SDLK_UNKNOWN
+                    && let Some(key) = sdl_key_to_egui(keycode)
+                    && (wants_keyboard || is_navigation_key(key))
+                {
+                    // The press was sent as a clipboard event
+                    if clipboard_shortcut(key, self.modifiers).is_some() {
+                        return true;
+                    }
+
+                    self.raw_input.events.push(egui::Event::Key {
+                        key,
+                        physical_key: Some(key),
+                        pressed: false,
+                        repeat: false,
+                        modifiers: self.modifiers,
+                    });
+                    handled = wants_keyboard;
+                }
+            }
+            SDL_EventType::DROP_BEGIN => {
+                // SDL only names the files once they are dropped, so egui is told that something
+                // is hovering without knowing what
+                self.raw_input.hovered_files = vec![egui::HoveredFile::default()];
+            }
+            SDL_EventType::DROP_POSITION => {
+                // No mouse motion arrives while dragging, this keeps drop targets under the
+                // cursor highlighted
+                let pos = unsafe { self.window_to_points(event.drop.x, event.drop.y) };
+                self.set_cursor_pos(pos);
+                self.raw_input
+                    .events
+                    .push(egui::Event::PointerMoved(self.cursor_pos));
+            }
+            SDL_EventType::DROP_FILE => {
+                let data = unsafe { event.drop.data };
+                if !data.is_null() {
+                    let path = unsafe { CStr::from_ptr(data) }.to_string_lossy();
+                    self.raw_input.dropped_files.push(egui::DroppedFile {
+                        path: Some(path.into_owned().into()),
+                        ..Default::default()
+                    });
+                }
+            }
+            SDL_EventType::DROP_COMPLETE => {
+                // Also sent when the drag left the window or was cancelled
+                self.raw_input.hovered_files.clear();
+            }
+            SDL_EventType::WINDOW_MOUSE_LEAVE => {
+                // The software cursor is drawn at egui's hover position, which would otherwise
+                // stay at the edge the mouse left through, along with any hover highlight
+                self.raw_input.events.push(egui::Event::PointerGone);
+            }
+            SDL_EventType::WINDOW_DISPLAY_SCALE_CHANGED => {
+                // Sent when the system scale changes or the window moves to a display with
+                // another one, whose pixel density can differ as well. egui lays out the fonts
+                // again at the new scale.
+                self.update_window_size(self.window);
+            }
+            SDL_EventType::WINDOW_SAFE_AREA_CHANGED => {
+                self.update_safe_area();
+                self.ctx.request_repaint();
+            }
+            SDL_EventType::LOCALE_CHANGED => {
+                // The next pass can pick up translations or layouts for the new locale
+                self.locales = preferred_locales();
+                self.ctx.request_repaint();
+            }
+            SDL_EventType::WINDOW_FOCUS_LOST => {
+                // Don't keep the cursor trapped in a window the user switched away from
+                self.set_cursor_grab(false);
+            }
+            SDL_EventType::WINDOW_EXPOSED => {
+                // The window contents may be gone, even if nothing in egui changed
+                self.repaint_forced = true;
+                self.ctx.request_repaint();
+            }
+            SDL_EventType::RENDER_DEVICE_RESET => {
+                // The contents of all textures were lost along with the device, registered ones
+                // included
+                self.rebuild_textures();
+            }
+            SDL_EventType::SYSTEM_THEME_CHANGED => {
+                self.update_system_theme();
+            }
+            SDL_EventType::KEYMAP_CHANGED => {
+                // Keys are translated from keycodes on every event, so only the modifier state
+                // can be stale after a layout switch
+                self.modifiers = get_modifiers(self.ctrl_is_command);
+                self.raw_input.modifiers = self.modifiers;
+            }
+            SDL_EventType::TEXT_INPUT => unsafe {
+                self.modifiers = get_modifiers(self.ctrl_is_command);
+                self.raw_input.modifiers = self.modifiers;
+                // Some platforms send text for shortcuts such as Ctrl+C. AltGr shows up as
+                // Ctrl+Alt on Windows and is needed to type e.g. '@' on many layouts.
+                let shortcut =
+                    (self.modifiers.command || self.modifiers.ctrl) && !self.modifiers.alt;
+                if self.ctx.wants_keyboard_input() && (!shortcut || self.ime_composing) {
+                    let text = event.text.text;
+                    let text = CStr::from_ptr(text);
+                    if let Ok(text) = text.to_str() {
+                        // The text that ends a composition replaces the preedit text
+                        let event = if std::mem::take(&mut self.ime_composing) {
+                            egui::Event::Ime(egui::ImeEvent::Commit(text.to_string()))
+                        } else {
+                            egui::Event::Text(text.to_string())
+                        };
+                        self.raw_input.events.push(event);
+                        self.text_received = true;
+                        handled = true;
+                    }
+                }
+            },
+            SDL_EventType::TEXT_EDITING => unsafe {
+                // SDL hands over the whole composition however long it is, `start` and
+                // `length` only describe the selection within it
+                let text = event.edit.text;
+                if self.ctx.wants_keyboard_input() && !text.is_null() {
+                    let text = CStr::from_ptr(text).to_string_lossy().into_owned();
+                    if !self.ime_composing && !text.is_empty() {
+                        self.ime_composing = true;
+                        self.raw_input
+                            .events
+                            .push(egui::Event::Ime(egui::ImeEvent::Enabled));
+                    }
+                    if self.ime_composing {
+                        self.raw_input
+                            .events
+                            .push(egui::Event::Ime(egui::ImeEvent::Preedit(text)));
+                        handled = true;
+                    }
+                }
+            },
+            _ => {}
+        }
+
+        handled
+    }
+
+    fn set_cursor_pos(&mut self, pos: egui::Pos2) {
+        let screen_rect = self.ctx.screen_rect();
+        self.cursor_pos.x = pos.x.clamp(screen_rect.min.x, screen_rect.max.x - 1.0);
+        self.cursor_pos.y = pos.y.clamp(screen_rect.min.y, screen_rect.max.y - 1.0);
+    }
+
+    /* Button events carry the exact position of the click, which the last motion event may
+     * lag behind. In relative mode the position is pinned and the tracked one is used. */
+    fn button_pos(&mut self, button: &SDL_MouseButtonEvent) -> egui::Pos2 {
+        if !self.is_relative_mouse_mode() {
+            self.set_cursor_pos(self.window_to_points(button.x, button.y));
+        }
+        self.cursor_pos
+    }
+
+    fn handle_finger(&mut self, finger: &SDL_TouchFingerEvent, phase: egui::TouchPhase) -> bool {
+        // Touches synthesized from the mouse would duplicate the mouse events
+        if finger.touchID == SDL_MOUSE_TOUCHID {
+            return false;
+        }
+
+        // Finger positions are normalized to the window
+        let pos =
+            self.window_to_points(finger.x * self.window_size.x, finger.y * self.window_size.y);
+        self.raw_input.events.push(egui::Event::Touch {
+            device_id: egui::TouchDeviceId(finger.touchID),
+            id: egui::TouchId(finger.fingerID),
+            phase,
+            pos,
+            force: Some(finger.pressure),
+        });
+
+        // The first finger down acts as the primary pointer button, replacing the mouse events
+        // SDL would otherwise synthesize for it
+        let is_pointer = self.pointer_finger == Some(finger.fingerID);
+        match phase {
+            egui::TouchPhase::Start if self.prefer_touch && self.pointer_finger.is_none() => {
+                self.pointer_finger = Some(finger.fingerID);
+                self.cursor_pos = pos;
+                self.raw_input.events.push(egui::Event::PointerMoved(pos));
+                self.raw_input.events.push(egui::Event::PointerButton {
+                    pos,
+                    button: egui::PointerButton::Primary,
+                    pressed: true,
+                    modifiers: self.modifiers,
+                });
+            }
+            egui::TouchPhase::Move if is_pointer => {
+                self.cursor_pos = pos;
+                self.raw_input.events.push(egui::Event::PointerMoved(pos));
+            }
+            egui::TouchPhase::End | egui::TouchPhase::Cancel if is_pointer => {
+                self.pointer_finger = None;
+                if phase == egui::TouchPhase::End {
+                    self.raw_input.events.push(egui::Event::PointerButton {
+                        pos,
+                        button: egui::PointerButton::Primary,
+                        pressed: false,
+                        modifiers: self.modifiers,
+                    });
+                }
+                self.raw_input.events.push(egui::Event::PointerGone);
+            }
+            _ => {}
+        }
+
+        self.ctx.is_pointer_over_area() || self.ctx.wants_pointer_input()
+    }
+
+    fn input_screen_rect(&self) -> Option<egui::Rect> {
+        if self.screen_size_pixels.x <= 0.0 || self.screen_size_pixels.y <= 0.0 {
+            return None;
+        }
+        // The screen is tracked in pixels, so zooming in egui changes the size in points
+        Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            self.screen_size_pixels / self.input_pixels_per_point(),
+        ))
+    }
+
+    /* The size of the window in pixels, as last reported by SDL. */
+    pub fn screen_size_pixels(&self) -> (u32, u32) {
+        (
+            self.screen_size_pixels.x.round() as u32,
+            self.screen_size_pixels.y.round() as u32,
+        )
+    }
+
+    /* The screen egui lays out the next pass in, in points. Empty until the window has a size. */
+    pub fn screen_rect_points(&self) -> egui::Rect {
+        self.input_screen_rect().unwrap_or(egui::Rect::ZERO)
+    }
+
+    /* The following feed egui input that didn't come from SDL, e.g. from a remote client. They
+     * can be mixed with `handle_event`, whichever comes last wins. */
+
+    /* Adds an event to the input of the next pass. */
+    pub fn push_event(&mut self, event: egui::Event) {
+        self.raw_input.events.push(event);
+    }
+
+    /* Lays out the next passes in `rect`, in points at the current scale, until the window
+     * is resized. egui's screen always starts at the origin, so only the size is used. */
+    pub fn set_screen_rect(&mut self, rect: egui::Rect) {
+        let pixels = rect.size() * self.input_pixels_per_point();
+        if pixels.x > 0.0 && pixels.y > 0.0 {
+            self.screen_size_pixels = pixels;
+        }
+    }
+
+    pub fn set_modifiers(&mut self, modifiers: egui::Modifiers) {
+        self.modifiers = modifiers;
+        self.raw_input.modifiers = modifiers;
+    }
+
+    /* Moves egui's pointer to `pos` in points, where following button events are placed. */
+    pub fn set_pointer_pos(&mut self, pos: egui::Pos2) {
+        self.set_cursor_pos(pos);
+        self.raw_input
+            .events
+            .push(egui::Event::PointerMoved(self.cursor_pos));
+    }
+
+    pub fn begin_pass(&mut self) -> egui::Context {
+        self.pass_started = self
+            .measure_timing
+            .then(|| unsafe { timer::SDL_GetPerformanceCounter() });
+        if let Some(factor) = self.scroll_smoothing {
+            self.flush_scroll(factor);
+        }
+        let offscreen = self.offscreen_size.take();
+        if let Some((size, pixels_per_point)) = offscreen {
+            let points = size / (pixels_per_point * self.ctx.zoom_factor());
+            self.raw_input.screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, points));
+            let viewport = self.raw_input.viewports.entry(egui::ViewportId::ROOT);
+            viewport.or_default().native_pixels_per_point = Some(pixels_per_point);
+        } else {
+            // Back to the window after a pass for a texture
+            if self.offscreen_pass {
+                self.update_native_pixels_per_point();
+            }
+            if let Some(screen_rect) = self.input_screen_rect() {
+                self.raw_input.screen_rect = Some(screen_rect);
+            }
+        }
+        self.offscreen_pass = offscreen.is_some();
+        if let Some(radius) = self.double_click_radius {
+            let points = self.window_to_points(radius, 0.0).x;
+            self.ctx
+                .options_mut(|o| o.input_options.max_click_dist = points);
+        }
         self.ctx.begin_pass(self.raw_input.take());
+        self.text_consumed = std::mem::take(&mut self.text_received);
+        self.needs_repaint = std::mem::take(&mut self.repaint_forced);
+
+        // egui only applies a new zoom factor at the start of a pass, so a zoom past the limits
+        // is undone from the next pass on
+        let zoom_factor = self.ctx.zoom_factor();
+        let clamped = zoom_factor.clamp(self.zoom_range.0, self.zoom_range.1);
+        if clamped != zoom_factor {
+            self.ctx.set_zoom_factor(clamped);
+        }
         self.ctx.clone()
     }
 
+    /* Whether an egui text field had focus in the last pass. egui's `wants_keyboard_input` is
+     * also true for other focused widgets such as buttons, which only react to a few keys, so
+     * games can keep using e.g. WASD for movement while this is false. */
+    pub fn wants_text_input(&self) -> bool {
+        self.wants_text_input
+    }
+
+    /* True when the window has to be redrawn because SDL reported it exposed, until the
+     * next pass starts. Applications that only render on changes should check this too. */
+    pub fn repaint_forced(&self) -> bool {
+        self.repaint_forced
+    }
+
+    /* Whether the frame from the last `end_pass` looks different from the one rendered last,
+     * with `draw` or through `take_primitives`, uses new textures, or egui wants another pass
+     * right away, e.g. while animating. Applications that render lazily can skip `draw` and
+     * presenting while this is false, and wait for the next event or `repaint_after` before
+     * running another pass. Also true after the window was exposed, see `repaint_forced`. */
+    pub fn needs_repaint(&self) -> bool {
+        self.needs_repaint || self.repaint_forced
+    }
+
+    /* How long egui is fine with waiting for the next pass without new input, as of the last
+     * `end_pass`. Zero while egui wants to repaint immediately, `Duration::MAX` while idle. */
+    pub fn repaint_after(&self) -> std::time::Duration {
+        self.repaint_after
+    }
+
+    /* Whether the input of the current pass had text typed into egui, so the application can
+     * skip its own text handling for the frame. */
+    pub fn text_consumed_this_frame(&self) -> bool {
+        self.text_consumed
+    }
+
+    /* The UI zoom, as changed with command +/-/0. */
+    pub fn zoom_factor(&self) -> f32 {
+        self.ctx.zoom_factor()
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.ctx.set_zoom_factor(1.0);
+    }
+
+    /* Limits for the UI zoom, egui's own limits of 0.2 to 5.0 by default. */
+    pub fn set_zoom_range(&mut self, min: f32, max: f32) {
+        self.zoom_range = (min, max.max(min));
+    }
+
+    /* Key events carry the modifiers as they were when the key was pressed, which the global
+     * state may already be ahead of once the event is polled. */
+    fn update_key_modifiers(&mut self, mod_state: SDL_Keymod) {
+        self.modifiers = modifiers_from(mod_state, self.ctrl_is_command);
+        self.raw_input.modifiers = self.modifiers;
+    }
+
+    fn is_zoom_shortcut(&self, keycode: SDL_Keycode) -> bool {
+        let zoom_key = matches!(
+            sdl_key_to_egui(keycode),
+            Some(egui::Key::Plus | egui::Key::Equals | egui::Key::Minus | egui::Key::Num0)
+        );
+        zoom_key && self.modifiers.command && self.ctx.options(|o| o.zoom_with_keyboard)
+    }
+
+    /* Runs a throwaway pass that lays out the printable Latin-1 range in every text style and
+     * uploads the resulting font atlas, so the first visible frame doesn't hitch on it. Must be
+     * called once the renderer exists, before the first `begin_pass`.
+     * SAFETY: This needs to be called from main thread */
+    pub fn prewarm_fonts(&mut self, renderer: *mut render::SDL_Renderer) {
+        let raw_input = egui::RawInput {
+            viewports: self.raw_input.viewports.clone(),
+            screen_rect: self.input_screen_rect(),
+            time: self.raw_input.time,
+            ..Default::default()
+        };
+        let text: String = (' '..='~').chain('\u{a0}'..='\u{ff}').collect();
+        let output = self.ctx.run(raw_input, |ctx| {
+            for font_id in ctx.style().text_styles.values() {
+                ctx.fonts(|fonts| {
+                    fonts.layout_no_wrap(text.clone(), font_id.clone(), egui::Color32::WHITE)
+                });
+            }
+        });
+
+        let mut stats = FrameStats::default();
+        self.update_textures(renderer, output.textures_delta, &mut stats);
+    }
+
+    /* Lays out `text` on a single line and returns its size in points. egui only creates its
+     * fonts in the first pass, so this is None until then, or until `prewarm_fonts` ran. */
+    pub fn galley_size(
+        &self,
+        text: impl Into<String>,
+        font_id: egui::FontId,
+    ) -> Option<egui::Vec2> {
+        if self.ctx.cumulative_pass_nr() == 0 {
+            return None;
+        }
+        let galley = self
+            .ctx
+            .fonts(|fonts| fonts.layout_no_wrap(text.into(), font_id, egui::Color32::PLACEHOLDER));
+        Some(galley.size())
+    }
+
     /* SAFETY: This needs to be called from main thread */
     pub fn end_pass(&mut self) {
+        debug_assert!(!self.window.is_null(), "end_pass without a window");
+        if self.window.is_null() {
+            return;
+        }
         let output = self.ctx.end_pass();
+        let pass_ended = self
+            .measure_timing
+            .then(|| unsafe { timer::SDL_GetPerformanceCounter() });
         for cmd in output.platform_output.commands {
-            match cmd {
-                OutputCommand::CopyText(text) => {
-                    if let Ok(text) = std::ffi::CString::new(text) {
+            if let OutputCommand::CopyText(text) = cmd {
+                self.clipboard.set_text(&text);
+            }
+        }
+
+        self.focus_gained.clear();
+        for event in &output.platform_output.events {
+            if let egui::output::OutputEvent::FocusGained(info) = event {
+                self.focus_gained.push(info.clone());
+            }
+            if let Some(on_widget_event) = &mut self.on_widget_event {
+                on_widget_event(event);
+            }
+        }
+
+        if let Some(viewport) = output.viewport_output.get(&egui::ViewportId::ROOT) {
+            for cmd in &viewport.commands {
+                match cmd {
+                    egui::ViewportCommand::Icon(Some(icon)) => self.set_window_icon(icon),
+                    egui::ViewportCommand::InnerSize(size) => {
+                        let size = self.points_to_window(*size);
                         unsafe {
-                            if !clipboard::SDL_SetClipboardText(text.as_ptr()) {
-                                println!("{:?}", SDL_GetError());
-                            };
-                        }
+                            video::SDL_SetWindowSize(
+                                self.window,
+                                size.x.round() as i32,
+                                size.y.round() as i32,
+                            )
+                        };
                     }
+                    egui::ViewportCommand::OuterPosition(pos) => {
+                        let pos = self.points_to_window(pos.to_vec2());
+                        unsafe {
+                            video::SDL_SetWindowPosition(
+                                self.window,
+                                pos.x.round() as i32,
+                                pos.y.round() as i32,
+                            )
+                        };
+                    }
+                    egui::ViewportCommand::Minimized(true) => unsafe {
+                        video::SDL_MinimizeWindow(self.window);
+                    },
+                    egui::ViewportCommand::Maximized(true) => unsafe {
+                        video::SDL_MaximizeWindow(self.window);
+                    },
+                    egui::ViewportCommand::Minimized(false)
+                    | egui::ViewportCommand::Maximized(false) => unsafe {
+                        video::SDL_RestoreWindow(self.window);
+                    },
+                    // SDL can't lock the cursor in place without relative mouse mode, so it is
+                    // confined to the window either way
+                    egui::ViewportCommand::CursorGrab(grab) => {
+                        self.set_cursor_grab(*grab != egui::viewport::CursorGrab::None);
+                    }
+                    egui::ViewportCommand::Decorations(decorations) => unsafe {
+                        video::SDL_SetWindowBordered(self.window, *decorations);
+                    },
+                    // SDL has no always on bottom, it is treated like a normal window
+                    egui::ViewportCommand::WindowLevel(level) => unsafe {
+                        video::SDL_SetWindowAlwaysOnTop(
+                            self.window,
+                            *level == egui::WindowLevel::AlwaysOnTop,
+                        );
+                    },
+                    _ => {}
                 }
-                _ => {}
-            }
-        }
-
-        if !self.cursor.ptr.is_null() {
-            use sdl3_sys::mouse::SDL_SystemCursor;
-            let new_cursor_look = match output.platform_output.cursor_icon {
-                egui::CursorIcon::Crosshair => SDL_SystemCursor::CROSSHAIR,
-                egui::CursorIcon::Default => SDL_SystemCursor::DEFAULT,
-                egui::CursorIcon::Grab => SDL_SystemCursor::POINTER,
-                egui::CursorIcon::Grabbing => SDL_SystemCursor::MOVE,
-                egui::CursorIcon::Move => SDL_SystemCursor::MOVE,
-                egui::CursorIcon::PointingHand => SDL_SystemCursor::POINTER,
-                egui::CursorIcon::ResizeHorizontal => SDL_SystemCursor::EW_RESIZE,
-                egui::CursorIcon::ResizeNeSw => SDL_SystemCursor::NESW_RESIZE,
-                egui::CursorIcon::ResizeNwSe => SDL_SystemCursor::NWSE_RESIZE,
-                egui::CursorIcon::ResizeVertical => SDL_SystemCursor::NS_RESIZE,
-                egui::CursorIcon::Text => SDL_SystemCursor::TEXT,
-                egui::CursorIcon::NotAllowed | egui::CursorIcon::NoDrop => {
-                    SDL_SystemCursor::NOT_ALLOWED
-                }
-                egui::CursorIcon::Wait => SDL_SystemCursor::WAIT,
-                _ => SDL_SystemCursor::DEFAULT,
-            };
+            }
+        }
 
-            if new_cursor_look != self.cursor.looks {
+        // Follow the focus of egui's text fields, which also shows and hides the on-screen
+        // keyboard on touch devices
+        // Only text fields ask for IME, other focused widgets such as buttons only want keys
+        let wants_text_input = output.platform_output.ime.is_some();
+        self.wants_text_input = wants_text_input;
+        if wants_text_input != self.text_input_active {
+            self.text_input_active = wants_text_input;
+            if self.manage_text_input {
                 unsafe {
-                    match Cursor::new(new_cursor_look) {
-                        Ok(cursor) => {
-                            self.cursor = cursor;
-                            mouse::SDL_SetCursor(self.cursor.ptr);
-                        }
-                        Err(e) => {
-                            match e.to_str() {
-                                Ok(text) => println!("Failed to set cursor: {}", text),
-                                _ => println!("Failed to set cursor"),
-                            };
-                        }
+                    if wants_text_input {
+                        SDL_StartTextInput(self.window);
+                    } else {
+                        SDL_StopTextInput(self.window);
                     }
                 }
             }
+            // Stopping text input cancels an unfinished composition
+            if !wants_text_input && std::mem::take(&mut self.ime_composing) {
+                self.raw_input
+                    .events
+                    .push(egui::Event::Ime(egui::ImeEvent::Disabled));
+            }
+        }
+
+        self.cursor_icon = output.platform_output.cursor_icon;
+        if self.manage_cursor {
+            self.update_cursor();
+        }
+
+        self.repaint_after = output
+            .viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .map_or(std::time::Duration::MAX, |viewport| viewport.repaint_delay);
+        // The scale the pass was laid out at, which a viewport command or zoom change during
+        // the pass only applies to the next one
+        let pixels_per_point = output.pixels_per_point;
+        let clipped_primitives = self.ctx.tessellate(output.shapes, pixels_per_point);
+        if let (Some(started), Some(ended)) = (self.pass_started.take(), pass_ended) {
+            self.pass_timing = Some(PassTiming {
+                pass: performance_duration(started, ended),
+                tessellation: performance_duration(ended, unsafe {
+                    timer::SDL_GetPerformanceCounter()
+                }),
+            });
+        }
+        // Comparing every vertex with the last drawn frame is left for when nothing else has
+        // decided yet
+        self.needs_repaint = self.needs_repaint
+            || self.repaint_after.is_zero()
+            || !output.textures_delta.is_empty()
+            || !self.deferred_textures.is_empty()
+            || pixels_per_point != self.last_pixels_per_point
+            || damage_between(&self.last_drawn, &clipped_primitives) != Some(egui::Rect::NOTHING);
+        self.last_pixels_per_point = pixels_per_point;
+
+        if self.track_damage {
+            self.update_damage(
+                &clipped_primitives,
+                pixels_per_point,
+                !output.textures_delta.is_empty(),
+            );
+        }
+        self.draw_info = Some(DrawInfo {
+            textures: output.textures_delta,
+            primitives: clipped_primitives,
+        });
+    }
+
+    /* Keeps the primitives of every frame to compare them with the next one, so only enable
+     * this when the damage rect is used. */
+    pub fn set_track_damage(&mut self, track_damage: bool) {
+        self.track_damage = track_damage;
+        self.last_frame = None;
+        self.damage = None;
+    }
+
+    /* The part of the screen in points that changed in the frame from the last `end_pass`,
+     * empty if nothing changed. None if the whole frame has to be presented, e.g. for the first
+     * frame, after a resize or when a texture changed, or if damage tracking is off. Changes to
+     * draw settings such as the global opacity aren't tracked. */
+    pub fn damage_rect(&self) -> Option<egui::Rect> {
+        self.damage
+    }
+
+    fn update_damage(
+        &mut self,
+        primitives: &[egui::ClippedPrimitive],
+        pixels_per_point: f32,
+        textures_changed: bool,
+    ) {
+        let screen_rect = self.ctx.screen_rect();
+        self.damage = self.last_frame.as_ref().and_then(|last| {
+            if textures_changed
+                || last.pixels_per_point != pixels_per_point
+                || last.screen_rect != screen_rect
+            {
+                return None;
+            }
+            damage_between(&last.primitives, primitives)
+        });
+        self.last_frame = Some(DamageState {
+            pixels_per_point,
+            screen_rect,
+            primitives: primitives.to_vec(),
+        });
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn update_cursor(&mut self) {
+        if self.cursor_icon == egui::CursorIcon::None || self.software_cursor {
+            if !self.cursor_hidden {
+                unsafe { mouse::SDL_HideCursor() };
+                self.cursor_hidden = true;
+            }
+            return;
+        }
+        if self.cursor_hidden {
+            unsafe { mouse::SDL_ShowCursor() };
+            self.cursor_hidden = false;
+        }
+        // The icon is usually the same for many frames in a row
+        if self.applied_cursor_icon == Some(self.cursor_icon) {
+            return;
+        }
+        let reapply = self.applied_cursor_icon.is_none();
+        self.applied_cursor_icon = Some(self.cursor_icon);
+
+        // Use the first cursor in the chain the platform can provide
+        for &looks in system_cursors(self.cursor_icon) {
+            let cursor = self.cursors.entry(looks).or_insert_with(|| {
+                Cursor::new(looks)
+                    .inspect_err(|e| {
+                        log::debug!(
+                            "System cursor {} unavailable, falling back: {}",
+                            looks.0,
+                            e.to_string_lossy()
+                        )
+                    })
+                    .ok()
+            });
+            if let Some(cursor) = cursor {
+                if looks != self.cursor_looks || reapply {
+                    unsafe { mouse::SDL_SetCursor(cursor.ptr) };
+                    self.cursor_looks = looks;
+                }
+                return;
+            }
+        }
+    }
+
+    /* Hands out the output of the last `end_pass` for rendering without `draw`, e.g. with a
+     * custom renderer. Textures in the delta are then the caller's responsibility. The frame
+     * counts as drawn for `needs_repaint`. */
+    pub fn take_primitives(
+        &mut self,
+    ) -> Option<(egui::TexturesDelta, Vec<egui::ClippedPrimitive>)> {
+        let (textures, primitives) = self.take_frame()?;
+        self.last_drawn = primitives.clone();
+        Some((textures, primitives))
+    }
+
+    /* Like `take_primitives`, for renderers in this crate, which set `last_drawn` themselves
+     * once they are done with the primitives instead of keeping a copy. */
+    fn take_frame(&mut self) -> Option<(egui::TexturesDelta, Vec<egui::ClippedPrimitive>)> {
+        self.draw_info
+            .take()
+            .map(|draw_info| (draw_info.textures, draw_info.primitives))
+    }
+
+    /* Destroys every SDL texture and has egui send the font atlas again in full with the next
+     * pass, e.g. after a render device reset lost their contents. Images from egui's image
+     * loaders are reloaded as well, but textures the application created with
+     * `Context::load_texture` have to be loaded again by the application.
+     * Textures from `register_texture` are destroyed as well, since egui can't restore them.
+     * `has_texture` is false for them until the application registers them again.
+     * SAFETY: This needs to be called from main thread */
+    pub fn rebuild_textures(&mut self) {
+        for (_, texture) in self.sdl_textures.drain() {
+            unsafe { SDL_DestroyTexture(texture) };
+        }
+        self.user_textures.clear();
+        self.free_cursor_textures();
+        self.reupload_textures();
+    }
+
+    /* Destroys all textures, including those from `register_texture`, whose ids become
+     * invalid. Use this before destroying the renderer, so a new one can be used afterwards.
+     * SAFETY: This needs to be called from main thread, while the renderer the textures were
+     * created with still exists. */
+    pub fn free_textures(&mut self) {
+        for (_, texture) in self.sdl_textures.drain() {
+            unsafe { SDL_DestroyTexture(texture) };
+        }
+        self.user_textures.clear();
+        self.free_cursor_textures();
+        self.renderer = ptr::null_mut();
+        self.reupload_textures();
+    }
+
+    /* Like `free_textures`, but only drops the texture pointers without destroying them, for
+     * when the renderer is already gone, which destroyed its textures along with it. Calling
+     * `free_textures` at that point would destroy dangling pointers. */
+    pub fn forget_textures(&mut self) {
+        self.sdl_textures.clear();
+        self.user_textures.clear();
+        for cursor in &mut self.software_cursors {
+            cursor.texture = ptr::null_mut();
+        }
+        self.renderer = ptr::null_mut();
+        self.reupload_textures();
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn free_cursor_textures(&mut self) {
+        for cursor in &mut self.software_cursors {
+            if !cursor.texture.is_null() {
+                unsafe { SDL_DestroyTexture(cursor.texture) };
+                cursor.texture = ptr::null_mut();
+            }
+        }
+    }
+
+    fn reupload_textures(&mut self) {
+        // The next renderer may prefer another format or support geometry after all
+        self.texture_format = None;
+        self.geometry_supported = None;
+        self.render_failed = false;
+        // Waiting uploads were meant for the textures that are gone, and would otherwise end up
+        // in the textures created after this
+        self.deferred_textures.clear();
+
+        // Fonts only exist once the first pass has run, until then the first delta is full anyway
+        if self.ctx.cumulative_pass_nr() > 0 {
+            let font_image = self.ctx.fonts(|fonts| fonts.image());
+            self.ctx.tex_manager().write().set(
+                TextureId::default(),
+                egui::epaint::ImageDelta::full(
+                    font_image,
+                    egui::epaint::TextureAtlas::texture_options(),
+                ),
+            );
+        }
+        self.ctx.forget_all_images();
+    }
+
+    /* Uploads an image for use with e.g. `egui::Image`, outside of egui's texture management.
+     * The filter defaults to the one set with `set_default_texture_filter`, which is linear
+     * unless changed. The texture lives until `unregister_texture`, or until a render device
+     * reset destroys it, see `rebuild_textures`.
+     * SAFETY: This needs to be called from main thread */
+    pub fn register_texture(
+        &mut self,
+        renderer: *mut render::SDL_Renderer,
+        image: &egui::ColorImage,
+        filter: Option<egui::TextureFilter>,
+        alpha: AlphaMode,
+    ) -> Option<TextureId> {
+        let texture_format = self.texture_format(renderer);
+        let id = self.create_user_texture(renderer, texture_format, image, filter, alpha);
+        self.evict_user_textures();
+        id
+    }
+
+    /* Like `register_texture` for many images at once, e.g. thumbnails on a loading screen. The
+     * ids are in the order of `images`, None where a texture couldn't be created.
+     * SAFETY: This needs to be called from main thread */
+    pub fn register_textures(
+        &mut self,
+        renderer: *mut render::SDL_Renderer,
+        images: &[(egui::ColorImage, Option<egui::TextureFilter>)],
+        alpha: AlphaMode,
+    ) -> Vec<Option<TextureId>> {
+        let texture_format = self.texture_format(renderer);
+        let ids = images
+            .iter()
+            .map(|(image, filter)| {
+                self.create_user_texture(renderer, texture_format, image, *filter, alpha)
+            })
+            .collect();
+        self.evict_user_textures();
+        ids
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn create_user_texture(
+        &mut self,
+        renderer: *mut render::SDL_Renderer,
+        texture_format: pixels::SDL_PixelFormat,
+        image: &egui::ColorImage,
+        filter: Option<egui::TextureFilter>,
+        alpha: AlphaMode,
+    ) -> Option<TextureId> {
+        let texture = create_texture(renderer, texture_format, image.size);
+        if texture.is_null() {
+            log::error!("Failed to create user texture: {}", sdl_error());
+            return None;
+        }
+        upload_user_image(texture, image, texture_format, alpha);
+        set_texture_filter(texture, filter.unwrap_or(self.default_texture_filter));
+
+        let id = TextureId::User(self.next_user_texture);
+        self.next_user_texture += 1;
+        self.sdl_textures.insert(id, texture);
+        self.user_textures.insert(
+            id,
+            UserTexture {
+                bytes: image.width() * image.height() * 4,
+                last_used: self.draw_count + 1,
+            },
+        );
+        Some(id)
+    }
+
+    /* Replaces the image of a texture from `register_texture`, keeping its filter. Returns false
+     * if `id` isn't a registered texture or the texture couldn't be resized.
+     * SAFETY: This needs to be called from main thread */
+    pub fn update_texture(
+        &mut self,
+        renderer: *mut render::SDL_Renderer,
+        id: TextureId,
+        image: &egui::ColorImage,
+        alpha: AlphaMode,
+    ) -> bool {
+        let texture_format = self.texture_format(renderer);
+        let Some(texture) = self
+            .sdl_textures
+            .get_mut(&id)
+            .filter(|_| matches!(id, TextureId::User(_)))
+        else {
+            return false;
+        };
+        if texture_size(*texture) != image.size {
+            let mut scale_mode = sdl3_sys::surface::SDL_SCALEMODE_LINEAR;
+            unsafe { render::SDL_GetTextureScaleMode(*texture, &mut scale_mode) };
+            let resized = create_texture(renderer, texture_format, image.size);
+            if resized.is_null() {
+                log::error!("Failed to resize user texture: {}", sdl_error());
+                return false;
+            }
+            unsafe {
+                SDL_DestroyTexture(*texture);
+                render::SDL_SetTextureScaleMode(resized, scale_mode);
+            }
+            *texture = resized;
+        }
+        upload_user_image(*texture, image, texture_format, alpha);
+        if let Some(user_texture) = self.user_textures.get_mut(&id) {
+            user_texture.bytes = image.width() * image.height() * 4;
+        }
+        self.evict_user_textures();
+        true
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    pub fn unregister_texture(&mut self, id: TextureId) {
+        if let TextureId::User(_) = id
+            && let Some(texture) = self.sdl_textures.remove(&id)
+        {
+            unsafe { SDL_DestroyTexture(texture) };
+            self.user_textures.remove(&id);
+        }
+    }
+
+    /* Once the textures from `register_texture` take up more than `bytes`, the ones that
+     * haven't been drawn for the longest time are destroyed, as if by `unregister_texture`.
+     * Textures registered since the last `draw` are kept, even if that leaves the budget
+     * exceeded. Use `has_texture` to find out whether an image has to be registered again.
+     * egui's own textures don't count towards the budget. None, the default, never evicts. */
+    pub fn set_user_texture_budget(&mut self, bytes: Option<usize>) {
+        self.user_texture_budget = bytes;
+        self.evict_user_textures();
+    }
+
+    /* Whether `id` can be drawn, false for textures that were unregistered, evicted or lost in a
+     * render device reset. */
+    pub fn has_texture(&self, id: TextureId) -> bool {
+        self.sdl_textures.contains_key(&id)
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn evict_user_textures(&mut self) {
+        let Some(budget) = self.user_texture_budget else {
+            return;
+        };
+        let mut used: usize = self.user_textures.values().map(|t| t.bytes).sum();
+        while used > budget {
+            let Some((&id, _)) = (self.user_textures.iter())
+                .filter(|(_, t)| t.last_used <= self.draw_count)
+                .min_by_key(|(_, t)| t.last_used)
+            else {
+                break;
+            };
+            log::debug!("Evicting user texture {id:?}");
+            used -= self.user_textures[&id].bytes;
+            self.unregister_texture(id);
         }
+    }
 
-        let clipped_primitives = self
-            .ctx
-            .tessellate(output.shapes.clone(), self.ctx.pixels_per_point());
-        self.draw_info = Some(DrawInfo {
-            textures: output.textures_delta,
-            primitives: clipped_primitives,
-        });
+    /* Filter for `register_texture` calls without one of their own. egui's own textures follow
+     * the `TextureOptions` they were loaded with instead. */
+    pub fn set_default_texture_filter(&mut self, filter: egui::TextureFilter) {
+        self.default_texture_filter = filter;
     }
 
     /* SAFETY: This needs to be called from main thread */
-    pub fn draw(&mut self, renderer: *mut render::SDL_Renderer) {
-        if self.draw_info.is_none() {
-            return;
+    fn texture_format(&mut self, renderer: *mut render::SDL_Renderer) -> pixels::SDL_PixelFormat {
+        self.bind_renderer(renderer);
+        *self
+            .texture_format
+            .get_or_insert_with(|| preferred_texture_format(renderer))
+    }
+
+    /* Textures are only valid for the renderer that created them, so a painter draws with one
+     * renderer until `free_textures` or `forget_textures` lets it move on to another. */
+    fn bind_renderer(&mut self, renderer: *mut render::SDL_Renderer) {
+        if self.renderer.is_null() {
+            self.renderer = renderer;
         }
+        assert!(
+            self.renderer == renderer,
+            "Painter used with a second renderer, use one painter per renderer"
+        );
+    }
 
-        let DrawInfo {
-            textures,
-            primitives,
-        } = self.draw_info.take().unwrap();
+    /* SAFETY: This needs to be called from main thread */
+    fn set_window_icon(&self, icon: &egui::IconData) {
+        let (width, height) = (icon.width as usize, icon.height as usize);
+        if width == 0 || height == 0 || icon.rgba.len() != width * height * 4 {
+            log::warn!(
+                "Ignoring malformed window icon of size {}x{}",
+                width,
+                height
+            );
+            return;
+        }
 
-        let mut render_scale_x = 0.0;
-        let mut render_scale_y = 0.0;
         unsafe {
-            SDL_GetRenderScale(
-                renderer,
-                addr_of_mut!(render_scale_x),
-                addr_of_mut!(render_scale_y),
+            // SDL copies the pixels when setting the icon, so the surface can borrow them
+            let surface = SDL_CreateSurfaceFrom(
+                width as i32,
+                height as i32,
+                pixels::SDL_PIXELFORMAT_RGBA32,
+                icon.rgba.as_ptr() as *mut std::ffi::c_void,
+                (width * 4) as i32,
             );
-            SDL_SetRenderScale(renderer, 1.0, 1.0);
+            if surface.is_null() {
+                log::error!("Failed to create window icon surface: {}", sdl_error());
+                return;
+            }
+            if !SDL_SetWindowIcon(self.window, surface) {
+                log::error!("Failed to set window icon: {}", sdl_error());
+            }
+            SDL_DestroySurface(surface);
         }
+    }
+
+    /* SAFETY: This needs to be called from main thread */
+    fn update_textures(
+        &mut self,
+        renderer: *mut render::SDL_Renderer,
+        textures: egui::TexturesDelta,
+        stats: &mut FrameStats,
+    ) {
+        let texture_format = self.texture_format(renderer);
 
         for (id, image_delta) in textures.set {
             match image_delta.image {
                 egui::ImageData::Color(ref color_image) => {
-                    let texture = self
-                        .sdl_textures
-                        .get(&id)
-                        .cloned()
-                        .unwrap_or_else(|| unsafe {
-                            SDL_CreateTexture(
-                                renderer,
-                                pixels::SDL_PIXELFORMAT_RGBA32,
-                                render::SDL_TEXTUREACCESS_STATIC,
-                                color_image.width() as i32,
-                                color_image.height() as i32,
-                            )
-                        });
+                    let mut existing = self.sdl_textures.get(&id).cloned();
+                    if let Some(texture) = existing {
+                        // A full delta may resize the texture, e.g. when the font atlas grows
+                        let size = texture_size(texture);
+                        if image_delta.pos.is_none() && size != color_image.size {
+                            unsafe { SDL_DestroyTexture(texture) };
+                            self.sdl_textures.remove(&id);
+                            stats.textures_freed += 1;
+                            existing = None;
+                        }
+                    }
+                    if existing.is_none() && image_delta.pos.is_some() {
+                        // Only part of a texture that no longer exists, e.g. after
+                        // `rebuild_textures`, the full image follows in a later delta
+                        continue;
+                    }
+                    let texture = existing.unwrap_or_else(|| {
+                        create_texture(renderer, texture_format, color_image.size)
+                    });
+                    if texture.is_null() {
+                        // Leave the texture untracked so the next full delta can retry
+                        log::error!("Failed to create texture {:?}: {}", id, sdl_error());
+                        continue;
+                    }
+                    stats.textures_created += existing.is_none() as usize;
+                    stats.textures_updated += existing.is_some() as usize;
 
-                    let sdl_pixels: Vec<u8> = color_image
-                        .pixels
-                        .iter()
-                        .flat_map(|color| [color.r(), color.g(), color.b(), color.a()])
-                        .collect();
+                    set_texture_filter(texture, image_delta.options.magnification);
+                    let sdl_pixels = texture_pixels(color_image, texture_format);
 
                     unsafe {
                         if let Some(rect) = image_delta.pos {
-                            let x = rect[0] as i32;
-                            let y = rect[1] as i32;
+                            // The pixels only cover the updated region, so both the rect and
+                            // the pitch follow the size of the sub-image
                             let rect = SDL_Rect {
-                                x,
-                                y,
-                                w: color_image.width() as i32 - x,
-                                h: color_image.height() as i32 - y,
+                                x: rect[0] as i32,
+                                y: rect[1] as i32,
+                                w: color_image.width() as i32,
+                                h: color_image.height() as i32,
                             };
 
                             // Update partial texture
@@ -432,90 +2753,847 @@ impl Painter {
             unsafe {
                 if let Some(t) = self.sdl_textures.get(&id).cloned() {
                     SDL_DestroyTexture(t);
+                    stats.textures_freed += 1;
                 }
             }
             self.sdl_textures.remove(&id);
         }
+    }
 
-        for egui::ClippedPrimitive {
-            clip_rect,
-            primitive,
-        } in &primitives
-        {
-            let clip = SDL_Rect {
-                x: clip_rect.min.x as i32,
-                y: clip_rect.min.y as i32,
-                w: (clip_rect.max.x - clip_rect.min.x) as i32,
-                h: (clip_rect.max.y - clip_rect.min.y) as i32,
+    /* Limits how many bytes of texture data `draw` uploads per frame, None for no limit. Uploads
+     * past the budget wait for the next frames, font atlas updates first, and meshes using a
+     * texture with waiting uploads are skipped until it is complete. At least one upload is
+     * done per frame, so larger images still arrive. */
+    pub fn set_upload_budget(&mut self, bytes_per_frame: Option<usize>) {
+        self.upload_budget = bytes_per_frame;
+    }
+
+    /* Splits off the uploads that don't fit this frame's budget and returns the rest, along
+     * with uploads left over from earlier frames. */
+    fn budget_textures(&mut self, textures: egui::TexturesDelta) -> egui::TexturesDelta {
+        let mut queue = std::mem::take(&mut self.deferred_textures);
+        queue.extend(textures.set);
+        // Nothing is left to upload for a freed texture
+        queue.retain(|(id, _)| !textures.free.contains(id));
+        let Some(budget) = self.upload_budget else {
+            return egui::TexturesDelta {
+                set: queue,
+                free: textures.free,
             };
-            unsafe { render::SDL_SetRenderClipRect(renderer, &clip) };
-
-            match primitive {
-                Primitive::Mesh(mesh) => {
-                    let sdl_vertices: Vec<SDL_Vertex> = mesh
-                        .vertices
-                        .iter()
-                        .map(|v| SDL_Vertex {
-                            position: SDL_FPoint {
-                                x: v.pos.x,
-                                y: v.pos.y,
-                            },
-                            color: SDL_FColor {
-                                r: v.color.r() as f32 / 255.0,
-                                g: v.color.g() as f32 / 255.0,
-                                b: v.color.b() as f32 / 255.0,
-                                a: v.color.a() as f32 / 255.0,
-                            },
-                            tex_coord: SDL_FPoint {
-                                x: v.uv.x,
-                                y: v.uv.y,
-                            },
-                        })
-                        .collect();
-
-                    let sdl_indices: Vec<i32> = mesh.indices.iter().map(|&i| i as i32).collect();
-
-                    let t = self
-                        .sdl_textures
-                        .get(&mesh.texture_id)
-                        .cloned()
-                        .unwrap_or_else(|| ptr::null_mut());
-                    unsafe {
-                        render::SDL_RenderGeometry(
-                            renderer,
-                            t,
-                            sdl_vertices.as_ptr(),
-                            sdl_vertices.len() as i32,
-                            sdl_indices.as_ptr(),
-                            sdl_indices.len() as i32,
-                        );
+        };
+
+        // The sort is stable, so updates to the same texture stay in order
+        queue.sort_by_key(|(id, _)| *id != TextureId::default());
+        let mut set = Vec::new();
+        let mut bytes = 0;
+        for (id, delta) in queue {
+            let size = match &delta.image {
+                egui::ImageData::Color(image) => image.pixels.len() * 4,
+            };
+            // Once one update to a texture waits, the ones after it have to wait as well
+            let waiting = self
+                .deferred_textures
+                .iter()
+                .any(|(deferred, _)| *deferred == id);
+            if waiting || (!set.is_empty() && bytes + size > budget) {
+                self.deferred_textures.push((id, delta));
+            } else {
+                bytes += size;
+                set.push((id, delta));
+            }
+        }
+        // Only a later `draw` uploads the rest, which lazy applications only call for a new pass
+        if !self.deferred_textures.is_empty() {
+            self.ctx.request_repaint();
+        }
+        egui::TexturesDelta {
+            set,
+            free: textures.free,
+        }
+    }
+
+    /* SAFETY: This needs to be called from main thread. `renderer` is only passed on to SDL,
+     * which checks that it is a live renderer before using it, so this stays a safe fn like the
+     * other functions taking SDL pointers. */
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn draw(&mut self, renderer: *mut render::SDL_Renderer) {
+        debug_assert!(!renderer.is_null(), "draw without a renderer");
+        if renderer.is_null() {
+            return;
+        }
+        self.frame_complete = false;
+        let Some((textures, primitives)) = self.take_frame() else {
+            return;
+        };
+        self.bind_renderer(renderer);
+        let mut stats = FrameStats::default();
+        // egui tessellates in points while the renderer is reset to draw in pixels
+        let pixels_per_point = self
+            .render_scale
+            .take()
+            .unwrap_or_else(|| self.ctx.pixels_per_point());
+
+        // The application may be in the middle of its own frame, so whatever egui changes is
+        // put back afterwards
+        let render_state = RenderState::save(renderer);
+        unsafe {
+            SDL_SetRenderScale(renderer, 1.0, 1.0);
+            render::SDL_SetRenderViewport(renderer, ptr::null());
+        }
+
+        let clear_color = match self.background {
+            Background::None => None,
+            Background::PanelFill => Some(self.ctx.style().visuals.panel_fill),
+            Background::Color(color) => Some(color),
+        };
+        if let Some(color) = clear_color {
+            let [r, g, b, a] = color.to_srgba_unmultiplied();
+            unsafe {
+                render::SDL_SetRenderClipRect(renderer, ptr::null());
+                render::SDL_SetRenderDrawColor(renderer, r, g, b, a);
+                render::SDL_RenderClear(renderer);
+            }
+        }
+
+        let textures = self.budget_textures(textures);
+        self.update_textures(renderer, textures, &mut stats);
+
+        let options = DrawOptions {
+            pixels_per_point,
+            opacity: self.opacity,
+            color_space: self.color_space,
+            fill_rect_fast_path: self.fill_rect_fast_path,
+            deferred_textures: self.deferred_textures.iter().map(|(id, _)| *id).collect(),
+        };
+        let mut rendered = true;
+        self.draw_count += 1;
+        for command in draw_commands(&primitives, &options, &mut stats) {
+            rendered &= match command {
+                DrawCommand::Geometry(batch) => {
+                    if let Some(user_texture) = self.user_textures.get_mut(&batch.texture) {
+                        user_texture.last_used = self.draw_count;
                     }
+                    let texture = self.sdl_textures.get(&batch.texture).cloned();
+                    // Registered textures go away when unregistered or evicted, drawing their
+                    // meshes untextured would show solid boxes instead
+                    if texture.is_none() && matches!(batch.texture, TextureId::User(_)) {
+                        if !std::mem::replace(&mut self.warned_missing_texture, true) {
+                            log::warn!("Skipping meshes of missing texture {:?}", batch.texture);
+                        }
+                        continue;
+                    }
+                    let texture = texture.unwrap_or(ptr::null_mut());
+                    let geometry_supported = *self
+                        .geometry_supported
+                        .get_or_insert_with(|| supports_geometry(renderer));
+                    batch.execute(renderer, texture, &mut stats, geometry_supported)
                 }
-                Primitive::Callback(_) => {
-                    unimplemented!()
-                }
+                DrawCommand::FillRects(fills) => fills.execute(renderer, &mut stats),
+            };
+        }
+
+        if self.software_cursor {
+            self.draw_software_cursor(renderer, pixels_per_point);
+        }
+
+        render_state.restore(renderer);
+        // Reported once, a renderer that can't draw won't start to later on
+        if !rendered && !self.render_failed {
+            self.render_failed = true;
+            self.report_fatal_error(&format!("Failed to render egui: {}", sdl_error()));
+        }
+        self.frame_complete = rendered && self.deferred_textures.is_empty();
+        self.frame_stats = stats;
+        self.last_drawn = primitives;
+    }
+
+    /* Whether the last `draw` rendered the whole frame from `end_pass`. False if there was no
+     * new frame to draw, rendering failed or texture uploads were held back by
+     * `set_upload_budget`, in which case the frame is finished by a later `draw`. Only a hint,
+     * together with `damage_rect` it lets the application decide what to present. */
+    pub fn frame_complete(&self) -> bool {
+        self.frame_complete
+    }
+
+    /* Fades everything `draw` renders, 0.0 is fully transparent. The background set with
+     * `set_background` and the software cursor are not affected. */
+    pub fn set_global_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    /* Draws meshes that are a single solid axis aligned rectangle with SDL_RenderFillRects
+     * instead of as geometry, which can be faster on the software renderer. egui merges the
+     * shapes sharing a clip rect into one mesh, so compare `FrameStats::fill_rects` with
+     * `FrameStats::meshes` to see whether it pays off for a given UI. Off by default. */
+    pub fn set_fill_rect_fast_path(&mut self, fill_rect_fast_path: bool) {
+        self.fill_rect_fast_path = fill_rect_fast_path;
+    }
+
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+    }
+
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    /* Makes only the next `draw` use the given pixels per point instead of the window's, e.g.
+     * to capture the UI at a higher resolution with `draw_to_texture`. Input and layout keep
+     * using the window scale, and text is sampled from the font atlas egui made for it. */
+    pub fn render_at_scale(&mut self, pixels_per_point: f32) {
+        self.render_scale = Some(pixels_per_point);
+    }
+
+    /* Lays out the next pass for a texture of `width` by `height` pixels at `pixels_per_point`
+     * instead of for the window, for use with `draw_to_texture` or `capture_to_image`. egui's
+     * zoom factor still applies on top. The pass after that is laid out for the window again.
+     * Input still arrives in window coordinates, so this is meant for passes that render
+     * something other than the interactive UI, e.g. a thumbnail or an export. */
+    pub fn set_offscreen_size(&mut self, width: u32, height: u32, pixels_per_point: f32) {
+        self.offscreen_size = Some((egui::vec2(width as f32, height as f32), pixels_per_point));
+    }
+
+    /* Like `draw`, but renders into `texture`, which must have been created with
+     * SDL_TEXTUREACCESS_TARGET. The texture is not cleared first.
+     * SAFETY: This needs to be called from main thread. Like in `draw`, the pointers are only
+     * passed on to SDL, which checks them. */
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn draw_to_texture(
+        &mut self,
+        renderer: *mut render::SDL_Renderer,
+        texture: *mut SDL_Texture,
+    ) {
+        unsafe {
+            let target = render::SDL_GetRenderTarget(renderer);
+            if !render::SDL_SetRenderTarget(renderer, texture) {
+                log::error!("Failed to set render target: {}", sdl_error());
+                return;
             }
+            self.draw(renderer);
+            render::SDL_SetRenderTarget(renderer, target);
+        }
+    }
+
+    /* Like `draw`, but renders into an offscreen texture and reads it back, e.g. for screenshots
+     * or visual regression tests. The image has the size of the window in pixels, or of the
+     * scale set with `render_at_scale`. Pixels egui doesn't cover are transparent unless a
+     * background is set. None if there is no frame to draw or SDL failed.
+     * SAFETY: This needs to be called from main thread. Like in `draw`, `renderer` is only passed
+     * on to SDL, which checks it. */
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn capture_to_image(
+        &mut self,
+        renderer: *mut render::SDL_Renderer,
+    ) -> Option<egui::ColorImage> {
+        self.draw_info.as_ref()?;
+        let pixels_per_point = self
+            .render_scale
+            .unwrap_or_else(|| self.ctx.pixels_per_point());
+        let size = (self.ctx.screen_rect().size() * pixels_per_point).round();
+        if size.x < 1.0 || size.y < 1.0 {
+            return None;
         }
 
         unsafe {
-            SDL_SetRenderScale(renderer, render_scale_x, render_scale_y);
+            let texture = SDL_CreateTexture(
+                renderer,
+                pixels::SDL_PIXELFORMAT_RGBA32,
+                render::SDL_TEXTUREACCESS_TARGET,
+                size.x as i32,
+                size.y as i32,
+            );
+            if texture.is_null() {
+                log::error!("Failed to create capture texture: {}", sdl_error());
+                return None;
+            }
+
+            let target = render::SDL_GetRenderTarget(renderer);
+            let (mut r, mut g, mut b, mut a) = (0, 0, 0, 0);
+            render::SDL_GetRenderDrawColor(renderer, &mut r, &mut g, &mut b, &mut a);
+            let mut surface = ptr::null_mut();
+            if render::SDL_SetRenderTarget(renderer, texture) {
+                render::SDL_SetRenderClipRect(renderer, ptr::null());
+                render::SDL_SetRenderDrawColor(renderer, 0, 0, 0, 0);
+                render::SDL_RenderClear(renderer);
+                self.draw(renderer);
+                surface = render::SDL_RenderReadPixels(renderer, ptr::null());
+            }
+            render::SDL_SetRenderTarget(renderer, target);
+            render::SDL_SetRenderDrawColor(renderer, r, g, b, a);
+            SDL_DestroyTexture(texture);
+
+            if surface.is_null() {
+                log::error!("Failed to read back egui: {}", sdl_error());
+                return None;
+            }
+            // Also converts from the colorspace of the render target, e.g. on HDR displays
+            let converted = SDL_ConvertSurface(surface, pixels::SDL_PIXELFORMAT_RGBA32);
+            SDL_DestroySurface(surface);
+            if converted.is_null() {
+                log::error!("Failed to convert egui capture: {}", sdl_error());
+                return None;
+            }
+
+            let (width, height) = ((*converted).w as usize, (*converted).h as usize);
+            let pitch = (*converted).pitch as usize;
+            let data = std::slice::from_raw_parts((*converted).pixels as *const u8, pitch * height);
+            let mut rgba = Vec::with_capacity(width * height * 4);
+            for row in data.chunks(pitch) {
+                rgba.extend_from_slice(&row[..width * 4]);
+            }
+            SDL_DestroySurface(converted);
+            // Textures blend premultiplied, so that's what ends up in the target
+            Some(egui::ColorImage::from_rgba_premultiplied(
+                [width, height],
+                &rgba,
+            ))
+        }
+    }
+
+    pub fn last_frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /* Measures how long passes and their tessellation take, for debug overlays. Off by default,
+     * as it reads the clock a few times per pass. */
+    pub fn set_measure_timing(&mut self, measure_timing: bool) {
+        self.measure_timing = measure_timing;
+        if !measure_timing {
+            self.pass_timing = None;
+        }
+    }
+
+    /* None until a pass was measured */
+    pub fn last_pass_timing(&self) -> Option<PassTiming> {
+        self.pass_timing
+    }
+}
+
+/* SDL clips to whole pixels. Rounding outward keeps the pixels a fractional clip edge passes
+ * through, which tessellated text and borders partly cover. */
+fn clip_to_pixels(clip_rect: egui::Rect) -> SDL_Rect {
+    let min = clip_rect.min.floor();
+    let max = clip_rect.max.ceil();
+    SDL_Rect {
+        x: min.x as i32,
+        y: min.y as i32,
+        w: (max.x - min.x) as i32,
+        h: (max.y - min.y) as i32,
+    }
+}
+
+/* Turns egui's primitives into SDL draw calls. Consecutive meshes with the same clip rect and
+ * texture are submitted as one call. */
+fn draw_commands(
+    primitives: &[egui::ClippedPrimitive],
+    options: &DrawOptions,
+    stats: &mut FrameStats,
+) -> Vec<DrawCommand> {
+    let pixels_per_point = options.pixels_per_point;
+    let mut commands = Vec::new();
+    let mut batch = GeometryBatch::default();
+    let mut fills = FillBatch::default();
+    for egui::ClippedPrimitive {
+        clip_rect,
+        primitive,
+    } in primitives
+    {
+        let clip_rect = *clip_rect * pixels_per_point;
+        if clip_rect.width() <= 0.0 || clip_rect.height() <= 0.0 {
+            continue;
+        }
+        let clip = clip_to_pixels(clip_rect);
+
+        match primitive {
+            Primitive::Mesh(mesh) => {
+                stats.meshes += 1;
+                if options.deferred_textures.contains(&mesh.texture_id) {
+                    continue;
+                }
+
+                if options.fill_rect_fast_path
+                    && let Some(rect) = solid_rect(mesh)
+                {
+                    batch.finish(&mut commands);
+                    let color =
+                        vertex_color(mesh.vertices[0].color, options.opacity, options.color_space);
+                    if fills.clip != clip || fills.color != color {
+                        fills.finish(&mut commands);
+                        fills.clip = clip;
+                        fills.color = color;
+                    }
+                    let rect = rect * pixels_per_point;
+                    fills.rects.push(SDL_FRect {
+                        x: rect.min.x,
+                        y: rect.min.y,
+                        w: rect.width(),
+                        h: rect.height(),
+                    });
+                    stats.fill_rects += 1;
+                    continue;
+                }
+                fills.finish(&mut commands);
+
+                stats.vertices += mesh.vertices.len();
+                stats.indices += mesh.indices.len();
+
+                if batch.clip != clip || batch.texture != mesh.texture_id || !batch.fits(mesh) {
+                    batch.finish(&mut commands);
+                    batch.clip = clip;
+                    batch.texture = mesh.texture_id;
+                }
+                batch.push(mesh, pixels_per_point, options.opacity, options.color_space);
+            }
+            Primitive::Callback(_) => {
+                unimplemented!()
+            }
+        }
+    }
+    batch.finish(&mut commands);
+    fills.finish(&mut commands);
+    commands
+}
+
+/* egui's colors are premultiplied, so fading scales all four channels. */
+fn vertex_color(color: egui::Color32, opacity: f32, color_space: ColorSpace) -> SDL_FColor {
+    let [r, g, b, a] = match color_space {
+        ColorSpace::Srgb => color.to_normalized_gamma_f32(),
+        ColorSpace::Linear => egui::Rgba::from(color).to_array(),
+    };
+    SDL_FColor {
+        r: r * opacity,
+        g: g * opacity,
+        b: b * opacity,
+        a: a * opacity,
+    }
+}
+
+/* The rectangle covered by `mesh` if it is exactly two triangles spanning an axis aligned
+ * rectangle in one solid color. */
+fn solid_rect(mesh: &egui::Mesh) -> Option<egui::Rect> {
+    if mesh.texture_id != TextureId::default()
+        || mesh.vertices.len() != 4
+        || mesh.indices.len() != 6
+    {
+        return None;
+    }
+    let first = mesh.vertices[0];
+    if mesh
+        .vertices
+        .iter()
+        .any(|v| v.color != first.color || v.uv != egui::epaint::WHITE_UV)
+    {
+        return None;
+    }
+    let rect = egui::Rect::from_points(&mesh.vertices.iter().map(|v| v.pos).collect::<Vec<_>>());
+    if !rect.is_positive() {
+        return None;
+    }
+    let corner = |i: u32| {
+        let pos = mesh.vertices.get(i as usize)?.pos;
+        let x = [rect.min.x, rect.max.x].iter().position(|&x| x == pos.x)?;
+        let y = [rect.min.y, rect.max.y].iter().position(|&y| y == pos.y)?;
+        Some(x + 2 * y)
+    };
+    let mut corners = [0; 6];
+    for (corner_index, &i) in corners.iter_mut().zip(&mesh.indices) {
+        *corner_index = corner(i)?;
+    }
+    // Every vertex is on its own corner, and the two triangles only share the diagonal
+    let mut seen = [false; 4];
+    for &c in &corners {
+        seen[c] = true;
+    }
+    let (first, second) = corners.split_at(3);
+    let shared: Vec<usize> = first
+        .iter()
+        .filter(|c| second.contains(c))
+        .copied()
+        .collect();
+    let distinct = |t: &[usize]| t[0] != t[1] && t[1] != t[2] && t[0] != t[2];
+    let on_diagonal = shared.len() == 2 && shared[0] + shared[1] == 3;
+    (seen.iter().all(|&s| s) && distinct(first) && distinct(second) && on_diagonal).then_some(rect)
+}
+
+/* The union of the areas covered by the primitives that differ between two frames. Paint
+ * callbacks can draw anything, so they make the damage unknown. */
+fn damage_between(
+    old: &[egui::ClippedPrimitive],
+    new: &[egui::ClippedPrimitive],
+) -> Option<egui::Rect> {
+    let bounds = |primitive: &egui::ClippedPrimitive| match &primitive.primitive {
+        Primitive::Mesh(mesh) => Some(mesh.calc_bounds().intersect(primitive.clip_rect)),
+        Primitive::Callback(_) => None,
+    };
+    let mut damage = egui::Rect::NOTHING;
+    for i in 0..old.len().max(new.len()) {
+        let unchanged = match (old.get(i), new.get(i)) {
+            (Some(a), Some(b)) => {
+                a.clip_rect == b.clip_rect
+                    && matches!(
+                        (&a.primitive, &b.primitive),
+                        (Primitive::Mesh(a), Primitive::Mesh(b)) if a == b
+                    )
+            }
+            _ => false,
+        };
+        if !unchanged {
+            for primitive in [old.get(i), new.get(i)].into_iter().flatten() {
+                damage = damage.union(bounds(primitive)?);
+            }
+        }
+    }
+    Some(damage)
+}
+
+fn performance_duration(start: u64, end: u64) -> std::time::Duration {
+    let frequency = unsafe { timer::SDL_GetPerformanceFrequency() };
+    std::time::Duration::from_secs_f64(end.saturating_sub(start) as f64 / frequency as f64)
+}
+
+/* Shows `message` in a modal error dialog, for use with `PainterBuilder::with_on_fatal_error`.
+ * SAFETY: This needs to be called from main thread */
+pub fn show_error_message_box(message: &str) {
+    let message =
+        std::ffi::CString::new(message.replace('\0', "")).expect("Interior nul bytes were removed");
+    unsafe {
+        messagebox::SDL_ShowSimpleMessageBox(
+            messagebox::SDL_MESSAGEBOX_ERROR,
+            c"egui error".as_ptr(),
+            message.as_ptr(),
+            ptr::null_mut(),
+        );
+    }
+}
+
+/* System cursors to try for an egui cursor icon, best match first. */
+fn system_cursors(icon: egui::CursorIcon) -> &'static [SDL_SystemCursor] {
+    use egui::CursorIcon;
+    match icon {
+        CursorIcon::Crosshair => &[SDL_SystemCursor::CROSSHAIR, SDL_SystemCursor::DEFAULT],
+        CursorIcon::Grab | CursorIcon::PointingHand => {
+            &[SDL_SystemCursor::POINTER, SDL_SystemCursor::DEFAULT]
+        }
+        CursorIcon::Grabbing => &[
+            SDL_SystemCursor::MOVE,
+            SDL_SystemCursor::POINTER,
+            SDL_SystemCursor::DEFAULT,
+        ],
+        CursorIcon::Move => &[SDL_SystemCursor::MOVE, SDL_SystemCursor::DEFAULT],
+        CursorIcon::ResizeHorizontal => &[
+            SDL_SystemCursor::EW_RESIZE,
+            SDL_SystemCursor::MOVE,
+            SDL_SystemCursor::DEFAULT,
+        ],
+        CursorIcon::ResizeNeSw => &[
+            SDL_SystemCursor::NESW_RESIZE,
+            SDL_SystemCursor::MOVE,
+            SDL_SystemCursor::DEFAULT,
+        ],
+        CursorIcon::ResizeNwSe => &[
+            SDL_SystemCursor::NWSE_RESIZE,
+            SDL_SystemCursor::MOVE,
+            SDL_SystemCursor::DEFAULT,
+        ],
+        CursorIcon::ResizeVertical => &[
+            SDL_SystemCursor::NS_RESIZE,
+            SDL_SystemCursor::MOVE,
+            SDL_SystemCursor::DEFAULT,
+        ],
+        CursorIcon::Text => &[SDL_SystemCursor::TEXT, SDL_SystemCursor::DEFAULT],
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => {
+            &[SDL_SystemCursor::NOT_ALLOWED, SDL_SystemCursor::DEFAULT]
+        }
+        CursorIcon::Wait => &[
+            SDL_SystemCursor::WAIT,
+            SDL_SystemCursor::PROGRESS,
+            SDL_SystemCursor::DEFAULT,
+        ],
+        _ => &[SDL_SystemCursor::DEFAULT],
+    }
+}
+
+/* Renderers without geometry support fail every SDL_RenderGeometryRaw call, an empty one tells
+ * without drawing anything.
+ * SAFETY: This needs to be called from main thread */
+fn supports_geometry(renderer: *mut render::SDL_Renderer) -> bool {
+    let vertex = SDL_Vertex::default();
+    let supported = unsafe {
+        render::SDL_RenderGeometryRaw(
+            renderer,
+            ptr::null_mut(),
+            &vertex.position.x,
+            size_of::<SDL_Vertex>() as i32,
+            &vertex.color,
+            size_of::<SDL_Vertex>() as i32,
+            ptr::null(),
+            0,
+            0,
+            ptr::null(),
+            0,
+            0,
+        )
+    };
+    if !supported {
+        log::warn!("SDL_RenderGeometryRaw unsupported, drawing rectangles only");
+    }
+    supported
+}
+
+/* SAFETY: This needs to be called from main thread */
+fn preferred_texture_format(renderer: *mut render::SDL_Renderer) -> pixels::SDL_PixelFormat {
+    // RGBA32 matches egui's memory layout, so only pick something else if the renderer lacks it
+    let mut has_bgra = false;
+    unsafe {
+        let props = render::SDL_GetRendererProperties(renderer);
+        let mut format = SDL_GetPointerProperty(
+            props,
+            render::SDL_PROP_RENDERER_TEXTURE_FORMATS_POINTER,
+            ptr::null_mut(),
+        ) as *const pixels::SDL_PixelFormat;
+        if format.is_null() {
+            return pixels::SDL_PIXELFORMAT_RGBA32;
+        }
+        while *format != pixels::SDL_PIXELFORMAT_UNKNOWN {
+            if *format == pixels::SDL_PIXELFORMAT_RGBA32 {
+                return pixels::SDL_PIXELFORMAT_RGBA32;
+            }
+            has_bgra |= *format == pixels::SDL_PIXELFORMAT_BGRA32;
+            format = format.add(1);
+        }
+    }
+
+    if has_bgra {
+        pixels::SDL_PIXELFORMAT_BGRA32
+    } else {
+        pixels::SDL_PIXELFORMAT_RGBA32
+    }
+}
+
+/* egui outputs premultiplied colors, so every texture blends that way.
+ * SAFETY: This needs to be called from main thread */
+fn create_texture(
+    renderer: *mut render::SDL_Renderer,
+    format: pixels::SDL_PixelFormat,
+    size: [usize; 2],
+) -> *mut SDL_Texture {
+    unsafe {
+        let texture = SDL_CreateTexture(
+            renderer,
+            format,
+            render::SDL_TEXTUREACCESS_STATIC,
+            size[0] as i32,
+            size[1] as i32,
+        );
+        if !texture.is_null() {
+            render::SDL_SetTextureBlendMode(
+                texture,
+                sdl3_sys::blendmode::SDL_BLENDMODE_BLEND_PREMULTIPLIED,
+            );
+        }
+        texture
+    }
+}
+
+/* SAFETY: This needs to be called from main thread */
+fn upload_user_image(
+    texture: *mut SDL_Texture,
+    image: &egui::ColorImage,
+    format: pixels::SDL_PixelFormat,
+    alpha: AlphaMode,
+) {
+    let sdl_pixels = match alpha {
+        AlphaMode::Premultiplied => texture_pixels(image, format),
+        AlphaMode::Straight => {
+            let premultiplied = egui::ColorImage::new(
+                image.size,
+                (image.pixels.iter())
+                    .map(|c| egui::Color32::from_rgba_unmultiplied(c.r(), c.g(), c.b(), c.a()))
+                    .collect(),
+            );
+            texture_pixels(&premultiplied, format)
         }
+    };
+    unsafe {
+        SDL_UpdateTexture(
+            texture,
+            ptr::null(),
+            sdl_pixels.as_ptr() as *const std::ffi::c_void,
+            (image.width() * 4) as i32,
+        );
+    }
+}
+
+fn texture_pixels(image: &egui::ColorImage, format: pixels::SDL_PixelFormat) -> Vec<u8> {
+    if format == pixels::SDL_PIXELFORMAT_BGRA32 {
+        image
+            .pixels
+            .iter()
+            .flat_map(|color| [color.b(), color.g(), color.r(), color.a()])
+            .collect()
+    } else {
+        image
+            .pixels
+            .iter()
+            .flat_map(|color| [color.r(), color.g(), color.b(), color.a()])
+            .collect()
     }
 }
 
+/* SAFETY: This needs to be called from main thread */
+fn set_texture_filter(texture: *mut SDL_Texture, filter: egui::TextureFilter) {
+    let scale_mode = match filter {
+        egui::TextureFilter::Nearest => sdl3_sys::surface::SDL_SCALEMODE_NEAREST,
+        egui::TextureFilter::Linear => sdl3_sys::surface::SDL_SCALEMODE_LINEAR,
+    };
+    unsafe { render::SDL_SetTextureScaleMode(texture, scale_mode) };
+}
+
+/* SAFETY: This needs to be called from main thread */
+fn texture_size(texture: *mut SDL_Texture) -> [usize; 2] {
+    let mut w = 0.0;
+    let mut h = 0.0;
+    unsafe { render::SDL_GetTextureSize(texture, &mut w, &mut h) };
+    [w as usize, h as usize]
+}
+
 /* SAFETY: Safe to call from any thread. Unsafe due to FFI only. */
-fn get_modifiers() -> egui::Modifiers {
+fn hint_number(name: *const std::ffi::c_char) -> Option<f64> {
+    let value = unsafe { hints::SDL_GetHint(name) };
+    if value.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(value) }.to_str().ok()?.parse().ok()
+}
+
+/* SAFETY: Safe to call from any thread. SDL keeps the error message per thread. */
+fn sdl_error() -> String {
+    unsafe { CStr::from_ptr(SDL_GetError()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn preferred_locales() -> Vec<Locale> {
+    let mut count = 0;
+    let locales = unsafe { locale::SDL_GetPreferredLocales(&mut count) };
+    if locales.is_null() {
+        log::debug!("Failed to get preferred locales: {}", sdl_error());
+        return Vec::new();
+    }
+    let to_string = |text: *const std::ffi::c_char| {
+        (!text.is_null()).then(|| {
+            unsafe { CStr::from_ptr(text) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    };
+    let result = (0..count.max(0) as usize)
+        .filter_map(|i| {
+            let locale = unsafe { &**locales.add(i) };
+            Some(Locale {
+                language: to_string(locale.language)?,
+                country: to_string(locale.country),
+            })
+        })
+        .collect();
+    unsafe { SDL_free(locales.cast()) };
+    result
+}
+
+/* egui's command shortcuts use Cmd on macOS and Ctrl elsewhere, unless `ctrl_is_command` is
+ * off, which leaves Ctrl to the application. */
+fn get_modifiers(ctrl_is_command: bool) -> egui::Modifiers {
+    // SAFETY: Safe to call from any thread. Unsafe due to FFI only.
     let mod_state = unsafe { SDL_GetModState() };
+    modifiers_from(mod_state, ctrl_is_command)
+}
+
+fn modifiers_from(mod_state: SDL_Keymod, ctrl_is_command: bool) -> egui::Modifiers {
     let alt = mod_state & (keycode::SDL_KMOD_LALT | keycode::SDL_KMOD_RALT) > 0;
     let shift = mod_state & (keycode::SDL_KMOD_LSHIFT | keycode::SDL_KMOD_RSHIFT) > 0;
     let ctrl = mod_state & (keycode::SDL_KMOD_LCTRL | keycode::SDL_KMOD_RCTRL) > 0;
+    let gui = mod_state & (keycode::SDL_KMOD_LGUI | keycode::SDL_KMOD_RGUI) > 0;
+
+    if cfg!(target_os = "macos") {
+        egui::Modifiers {
+            alt,
+            ctrl,
+            shift,
+            mac_cmd: gui,
+            command: gui,
+        }
+    } else {
+        egui::Modifiers {
+            alt,
+            ctrl,
+            shift,
+            command: ctrl && ctrl_is_command,
+            ..Default::default()
+        }
+    }
+}
 
-    egui::Modifiers {
-        alt,
-        ctrl,
-        shift,
-        command: ctrl,
-        ..Default::default()
+/* SAFETY: Safe to call from any thread. Unsafe due to FFI only. */
+fn get_lock_state() -> LockState {
+    let mod_state = unsafe { SDL_GetModState() };
+    LockState {
+        caps_lock: mod_state & keycode::SDL_KMOD_CAPS > 0,
+        num_lock: mod_state & keycode::SDL_KMOD_NUM > 0,
+    }
+}
+
+/* Maps the clipboard shortcuts to the C, X or V of the matching command shortcut. The Insert
+ * and Delete variants are common outside of macOS, some keyboards have dedicated keys. */
+fn clipboard_shortcut(key: egui::Key, modifiers: egui::Modifiers) -> Option<egui::Key> {
+    match key {
+        egui::Key::C | egui::Key::X | egui::Key::V if modifiers.command => Some(key),
+        egui::Key::Insert if modifiers.shift => Some(egui::Key::V),
+        egui::Key::Insert if modifiers.ctrl => Some(egui::Key::C),
+        egui::Key::Delete if modifiers.shift => Some(egui::Key::X),
+        egui::Key::Copy => Some(egui::Key::C),
+        egui::Key::Cut => Some(egui::Key::X),
+        egui::Key::Paste => Some(egui::Key::V),
+        _ => None,
+    }
+}
+
+/* The keys egui moves focus with or activates the focused widget with */
+fn is_navigation_key(key: egui::Key) -> bool {
+    use egui::Key::*;
+    matches!(
+        key,
+        Tab | ArrowUp | ArrowDown | ArrowLeft | ArrowRight | Enter | Escape | Space
+    )
+}
+
+/* With Num Lock off the keypad acts as navigation keys, SDL reports the same keycodes either way */
+fn keypad_key(key: SDL_Keycode, num_lock: bool) -> SDL_Keycode {
+    use sdl3_sys::keycode::*;
+    if num_lock {
+        return key;
+    }
+    match key {
+        SDLK_KP_0 => SDLK_INSERT,
+        SDLK_KP_1 => SDLK_END,
+        SDLK_KP_2 => SDLK_DOWN,
+        SDLK_KP_3 => SDLK_PAGEDOWN,
+        SDLK_KP_4 => SDLK_LEFT,
+        SDLK_KP_5 => SDLK_UNKNOWN,
+        SDLK_KP_6 => SDLK_RIGHT,
+        SDLK_KP_7 => SDLK_HOME,
+        SDLK_KP_8 => SDLK_UP,
+        SDLK_KP_9 => SDLK_PAGEUP,
+        SDLK_KP_PERIOD => SDLK_DELETE,
+        _ => key,
+    }
+}
+
+fn sdl_button_to_egui(button: u8) -> Option<egui::PointerButton> {
+    match button as i32 {
+        mouse::SDL_BUTTON_LEFT => Some(egui::PointerButton::Primary),
+        mouse::SDL_BUTTON_MIDDLE => Some(egui::PointerButton::Middle),
+        mouse::SDL_BUTTON_RIGHT => Some(egui::PointerButton::Secondary),
+        _ => None,
     }
 }
 
@@ -531,13 +3609,35 @@ fn sdl_key_to_egui(key: SDL_Keycode) -> Option<egui::Key> {
         SDLK_TAB => Key::Tab,
         SDLK_BACKSPACE => Key::Backspace,
         SDLK_SPACE => Key::Space,
-        SDLK_RETURN => Key::Enter,
+        SDLK_RETURN | SDLK_KP_ENTER => Key::Enter,
         SDLK_INSERT => Key::Insert,
         SDLK_HOME => Key::Home,
         SDLK_DELETE => Key::Delete,
         SDLK_END => Key::End,
         SDLK_PAGEDOWN => Key::PageDown,
         SDLK_PAGEUP => Key::PageUp,
+        SDLK_COPY => Key::Copy,
+        SDLK_CUT => Key::Cut,
+        SDLK_PASTE => Key::Paste,
+        SDLK_PLUS | SDLK_KP_PLUS => Key::Plus,
+        SDLK_EQUALS | SDLK_KP_EQUALS => Key::Equals,
+        SDLK_MINUS | SDLK_KP_MINUS => Key::Minus,
+        // egui has no multiply key, the character still arrives through TEXT_INPUT
+        SDLK_SLASH | SDLK_KP_DIVIDE => Key::Slash,
+        SDLK_PERIOD | SDLK_KP_PERIOD => Key::Period,
+        SDLK_COMMA | SDLK_KP_COMMA => Key::Comma,
+        SDLK_BACKSLASH => Key::Backslash,
+        SDLK_SEMICOLON => Key::Semicolon,
+        SDLK_COLON => Key::Colon,
+        SDLK_APOSTROPHE => Key::Quote,
+        SDLK_GRAVE => Key::Backtick,
+        SDLK_PIPE => Key::Pipe,
+        SDLK_QUESTION => Key::Questionmark,
+        SDLK_EXCLAIM => Key::Exclamationmark,
+        SDLK_LEFTBRACKET => Key::OpenBracket,
+        SDLK_RIGHTBRACKET => Key::CloseBracket,
+        SDLK_LEFTBRACE => Key::OpenCurlyBracket,
+        SDLK_RIGHTBRACE => Key::CloseCurlyBracket,
         SDLK_KP_0 | SDLK_0 => Key::Num0,
         SDLK_KP_1 | SDLK_1 => Key::Num1,
         SDLK_KP_2 | SDLK_2 => Key::Num2,
@@ -579,3 +3679,238 @@ fn sdl_key_to_egui(key: SDL_Keycode) -> Option<egui::Key> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(mesh: &egui::Mesh) -> GeometryBatch {
+        let mut batch = GeometryBatch::default();
+        batch.push(mesh, 1.0, 1.0, ColorSpace::default());
+        batch
+    }
+
+    #[test]
+    fn egui_rect_is_drawn_as_rect() {
+        let mut mesh = egui::Mesh::default();
+        let rect = egui::Rect::from_min_max(egui::pos2(10.0, 20.0), egui::pos2(30.0, 25.0));
+        let uv = egui::Rect::from_min_max(egui::pos2(0.25, 0.5), egui::pos2(0.5, 0.75));
+        mesh.add_rect_with_uv(rect, uv, egui::Color32::RED);
+
+        let (dst, uv_min, uv_max, _) = batch(&mesh).rect_at(0).unwrap();
+        assert_eq!((dst.x, dst.y, dst.w, dst.h), (10.0, 20.0, 20.0, 5.0));
+        assert_eq!(
+            (uv_min.x, uv_min.y, uv_max.x, uv_max.y),
+            (0.25, 0.5, 0.5, 0.75)
+        );
+    }
+
+    #[test]
+    fn triangles_that_are_not_a_rect_are_skipped() {
+        let mut mesh = egui::Mesh::default();
+        for pos in [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)] {
+            mesh.colored_vertex(egui::pos2(pos.0, pos.1), egui::Color32::RED);
+        }
+        // Sharing a side instead of the diagonal leaves part of the rect uncovered
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(0, 1, 3);
+        // A mesh of three indices moves the quads after it out of step
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(2, 3, 0);
+
+        let batch = batch(&mesh);
+        assert!(batch.rect_at(0).is_none());
+        assert!(batch.rect_at(6).is_none());
+    }
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> egui::Rect {
+        egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(w, h))
+    }
+
+    fn quad(rect: egui::Rect, texture: TextureId) -> egui::Mesh {
+        let mut mesh = egui::Mesh::with_texture(texture);
+        let uv = egui::Rect::from_min_max(egui::epaint::WHITE_UV, egui::epaint::WHITE_UV);
+        mesh.add_rect_with_uv(rect, uv, egui::Color32::RED);
+        mesh
+    }
+
+    fn clipped(clip_rect: egui::Rect, mesh: egui::Mesh) -> egui::ClippedPrimitive {
+        egui::ClippedPrimitive {
+            clip_rect,
+            primitive: Primitive::Mesh(mesh),
+        }
+    }
+
+    fn commands(
+        primitives: &[egui::ClippedPrimitive],
+        fill_rect_fast_path: bool,
+    ) -> Vec<DrawCommand> {
+        let options = DrawOptions {
+            pixels_per_point: 1.0,
+            opacity: 1.0,
+            color_space: ColorSpace::default(),
+            fill_rect_fast_path,
+            deferred_textures: Vec::new(),
+        };
+        draw_commands(primitives, &options, &mut FrameStats::default())
+    }
+
+    fn geometry(command: &DrawCommand) -> &GeometryBatch {
+        match command {
+            DrawCommand::Geometry(batch) => batch,
+            DrawCommand::FillRects(_) => panic!("expected geometry"),
+        }
+    }
+
+    fn fills(command: &DrawCommand) -> &FillBatch {
+        match command {
+            DrawCommand::FillRects(fills) => fills,
+            DrawCommand::Geometry(_) => panic!("expected fill rects"),
+        }
+    }
+
+    fn sdl_rect(rect: SDL_Rect) -> (i32, i32, i32, i32) {
+        (rect.x, rect.y, rect.w, rect.h)
+    }
+
+    #[test]
+    fn clip_rect_covers_partial_pixels() {
+        // Rounding either side inwards would cut off antialiased edges
+        let clip = egui::Rect::from_min_max(egui::pos2(1.25, 2.75), egui::pos2(10.5, 20.0));
+        assert_eq!(sdl_rect(clip_to_pixels(clip)), (1, 2, 10, 18));
+        let whole = rect(3.0, 4.0, 5.0, 6.0);
+        assert_eq!(sdl_rect(clip_to_pixels(whole)), (3, 4, 5, 6));
+        let negative = egui::Rect::from_min_max(egui::pos2(-0.5, -1.5), egui::pos2(0.5, 0.0));
+        assert_eq!(sdl_rect(clip_to_pixels(negative)), (-1, -2, 2, 2));
+    }
+
+    #[test]
+    fn clip_change_starts_a_batch() {
+        let white = TextureId::default();
+        let clip = rect(0.0, 0.0, 100.0, 100.0);
+        let other_clip = rect(0.0, 0.0, 50.0, 50.0);
+        let commands = commands(
+            &[
+                clipped(clip, quad(rect(0.0, 0.0, 10.0, 10.0), white)),
+                clipped(clip, quad(rect(10.0, 0.0, 10.0, 10.0), white)),
+                clipped(other_clip, quad(rect(20.0, 0.0, 10.0, 10.0), white)),
+            ],
+            false,
+        );
+
+        assert_eq!(commands.len(), 2);
+        let (first, second) = (geometry(&commands[0]), geometry(&commands[1]));
+        assert_eq!(sdl_rect(first.clip), (0, 0, 100, 100));
+        assert_eq!((first.vertices.len(), first.indices16.len()), (8, 12));
+        // The second quad's indices point past the first one's vertices
+        assert_eq!(first.indices16[6..], [4, 5, 6, 6, 5, 7]);
+        assert_eq!(sdl_rect(second.clip), (0, 0, 50, 50));
+        assert_eq!((second.vertices.len(), second.indices16.len()), (4, 6));
+    }
+
+    #[test]
+    fn texture_change_starts_a_batch() {
+        let clip = rect(0.0, 0.0, 100.0, 100.0);
+        let user = TextureId::User(1);
+        let commands = commands(
+            &[
+                clipped(clip, quad(rect(0.0, 0.0, 10.0, 10.0), TextureId::default())),
+                clipped(clip, quad(rect(10.0, 0.0, 10.0, 10.0), user)),
+                clipped(clip, quad(rect(20.0, 0.0, 10.0, 10.0), user)),
+            ],
+            false,
+        );
+
+        assert_eq!(commands.len(), 2);
+        assert_eq!(geometry(&commands[0]).texture, TextureId::default());
+        assert_eq!(geometry(&commands[1]).texture, user);
+        assert_eq!(geometry(&commands[1]).vertices.len(), 8);
+    }
+
+    #[test]
+    fn large_mesh_uses_32_bit_indices() {
+        let white = TextureId::default();
+        let clip = rect(0.0, 0.0, 100.0, 100.0);
+        let mut large = egui::Mesh::default();
+        for i in 0..20_000 {
+            large.add_colored_rect(rect(0.0, 0.0, 1.0, i as f32), egui::Color32::RED);
+        }
+        let commands = commands(
+            &[
+                clipped(clip, quad(rect(0.0, 0.0, 10.0, 10.0), white)),
+                clipped(clip, large),
+                clipped(clip, quad(rect(0.0, 0.0, 10.0, 10.0), white)),
+            ],
+            false,
+        );
+
+        // Batches stay within 16 bit indices unless a single mesh needs more
+        assert_eq!(commands.len(), 3);
+        let (before, large, after) = (
+            geometry(&commands[0]),
+            geometry(&commands[1]),
+            geometry(&commands[2]),
+        );
+        assert_eq!((before.indices16.len(), before.indices32.len()), (6, 0));
+        assert_eq!((large.indices16.len(), large.indices32.len()), (0, 120_000));
+        assert_eq!(large.indices32.iter().max(), Some(&79_999));
+        assert_eq!((after.indices16.len(), after.indices32.len()), (6, 0));
+    }
+
+    #[test]
+    fn empty_clip_is_skipped() {
+        let white = TextureId::default();
+        let commands = commands(
+            &[
+                clipped(
+                    rect(10.0, 10.0, 0.0, 50.0),
+                    quad(rect(10.0, 10.0, 5.0, 5.0), white),
+                ),
+                clipped(
+                    rect(10.0, 10.0, 50.0, 0.0),
+                    quad(rect(10.0, 10.0, 5.0, 5.0), white),
+                ),
+            ],
+            true,
+        );
+
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn fill_rects_keep_their_place_between_meshes() {
+        let white = TextureId::default();
+        let clip = rect(0.0, 0.0, 100.0, 100.0);
+        let commands = commands(
+            &[
+                clipped(clip, quad(rect(0.0, 0.0, 10.0, 10.0), white)),
+                clipped(clip, quad(rect(10.0, 0.0, 10.0, 10.0), white)),
+                clipped(clip, quad(rect(20.0, 0.0, 10.0, 10.0), TextureId::User(1))),
+                clipped(clip, quad(rect(30.0, 0.0, 10.0, 10.0), white)),
+            ],
+            true,
+        );
+
+        // Solid rects are drawn in order with the textured mesh between them
+        assert_eq!(commands.len(), 3);
+        assert_eq!(fills(&commands[0]).rects.len(), 2);
+        assert_eq!(geometry(&commands[1]).texture, TextureId::User(1));
+        let last = fills(&commands[2]);
+        assert_eq!(last.rects.len(), 1);
+        assert_eq!((last.rects[0].x, last.rects[0].w), (30.0, 10.0));
+    }
+
+    #[test]
+    fn keypad_follows_num_lock() {
+        use sdl3_sys::keycode::*;
+        assert_eq!(keypad_key(SDLK_KP_8, true), SDLK_KP_8);
+        assert_eq!(keypad_key(SDLK_KP_PERIOD, true), SDLK_KP_PERIOD);
+        assert_eq!(keypad_key(SDLK_KP_8, false), SDLK_UP);
+        assert_eq!(keypad_key(SDLK_KP_0, false), SDLK_INSERT);
+        assert_eq!(keypad_key(SDLK_KP_PERIOD, false), SDLK_DELETE);
+        // Keys that don't change with Num Lock
+        assert_eq!(keypad_key(SDLK_KP_ENTER, false), SDLK_KP_ENTER);
+        assert_eq!(keypad_key(SDLK_A, false), SDLK_A);
+    }
+}
@@ -0,0 +1,216 @@
+// Not every test uses every helper
+#![allow(dead_code)]
+
+use egui_sdl3::{Clipboard, Painter};
+use sdl3_sys::events::{
+    SDL_Event, SDL_EventType, SDL_KeyboardEvent, SDL_MouseButtonEvent, SDL_MouseMotionEvent,
+    SDL_MouseWheelEvent, SDL_TextInputEvent, SDL_WindowEvent,
+};
+use sdl3_sys::hints::{SDL_HINT_RENDER_DRIVER, SDL_HINT_VIDEO_DRIVER, SDL_SetHint};
+use sdl3_sys::init::{SDL_INIT_VIDEO, SDL_Init};
+use sdl3_sys::keycode::{SDL_KMOD_NONE, SDL_Keycode, SDL_Keymod};
+use sdl3_sys::mouse::SDL_BUTTON_LEFT;
+use sdl3_sys::render::{SDL_CreateRenderer, SDL_DestroyRenderer, SDL_Renderer};
+use sdl3_sys::video::{SDL_CreateWindow, SDL_DestroyWindow, SDL_Window};
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::ptr;
+use std::rc::Rc;
+use std::sync::{Mutex, MutexGuard};
+
+// SDL keeps global state, so tests that use it run one at a time
+static SDL_LOCK: Mutex<()> = Mutex::new(());
+
+/* A window on SDL's dummy video driver with a software renderer, so tests run without a
+ * display. Everything is destroyed on drop. */
+pub struct Headless {
+    pub window: *mut SDL_Window,
+    pub renderer: *mut SDL_Renderer,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Headless {
+    pub fn new(width: i32, height: i32) -> Self {
+        let lock = SDL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        unsafe {
+            SDL_SetHint(SDL_HINT_VIDEO_DRIVER, c"dummy".as_ptr());
+            SDL_SetHint(SDL_HINT_RENDER_DRIVER, c"software".as_ptr());
+            assert!(SDL_Init(SDL_INIT_VIDEO), "SDL_Init failed");
+            let window = SDL_CreateWindow(c"test".as_ptr(), width, height, 0);
+            assert!(!window.is_null(), "SDL_CreateWindow failed");
+            let renderer = SDL_CreateRenderer(window, ptr::null());
+            assert!(!renderer.is_null(), "SDL_CreateRenderer failed");
+            Self {
+                window,
+                renderer,
+                _lock: lock,
+            }
+        }
+    }
+}
+
+impl Drop for Headless {
+    fn drop(&mut self) {
+        unsafe {
+            SDL_DestroyRenderer(self.renderer);
+            SDL_DestroyWindow(self.window);
+        }
+    }
+}
+
+/* A clipboard that only lives in the test, shared between the painter and the test. */
+#[derive(Clone, Default)]
+pub struct MockClipboard(pub Rc<RefCell<Option<String>>>);
+
+impl Clipboard for MockClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.0.borrow().clone()
+    }
+
+    fn set_text(&mut self, text: &str) {
+        *self.0.borrow_mut() = Some(text.to_owned());
+    }
+}
+
+/* Runs one pass with the given UI, advancing the time by a frame. */
+pub fn run(painter: &mut Painter, frame: &mut f64, ui: impl FnOnce(&egui::Context)) {
+    *frame += 1.0 / 60.0;
+    painter.update_time(*frame);
+    let ctx = painter.begin_pass();
+    ui(&ctx);
+    painter.end_pass();
+}
+
+pub fn key_down(
+    painter: &mut Painter,
+    window: *mut SDL_Window,
+    key: SDL_Keycode,
+    r#mod: SDL_Keymod,
+) -> bool {
+    key_event(painter, window, SDL_EventType::KEY_DOWN, key, r#mod)
+}
+
+pub fn key_up(
+    painter: &mut Painter,
+    window: *mut SDL_Window,
+    key: SDL_Keycode,
+    r#mod: SDL_Keymod,
+) -> bool {
+    key_event(painter, window, SDL_EventType::KEY_UP, key, r#mod)
+}
+
+/* A press and release of `key` with the modifiers held. */
+pub fn press(painter: &mut Painter, window: *mut SDL_Window, key: SDL_Keycode, r#mod: SDL_Keymod) {
+    key_down(painter, window, key, r#mod);
+    key_up(painter, window, key, r#mod);
+}
+
+fn key_event(
+    painter: &mut Painter,
+    window: *mut SDL_Window,
+    r#type: SDL_EventType,
+    key: SDL_Keycode,
+    r#mod: SDL_Keymod,
+) -> bool {
+    let event = SDL_Event {
+        key: SDL_KeyboardEvent {
+            r#type,
+            key,
+            r#mod,
+            down: r#type == SDL_EventType::KEY_DOWN,
+            ..Default::default()
+        },
+    };
+    painter.handle_event(event, window)
+}
+
+pub fn text_input(painter: &mut Painter, window: *mut SDL_Window, text: &str) -> bool {
+    let text = CString::new(text).unwrap();
+    let event = SDL_Event {
+        text: SDL_TextInputEvent {
+            r#type: SDL_EventType::TEXT_INPUT,
+            text: text.as_ptr(),
+            ..Default::default()
+        },
+    };
+    painter.handle_event(event, window)
+}
+
+pub fn mouse_motion(painter: &mut Painter, window: *mut SDL_Window, x: f32, y: f32) -> bool {
+    let event = SDL_Event {
+        motion: SDL_MouseMotionEvent {
+            r#type: SDL_EventType::MOUSE_MOTION,
+            x,
+            y,
+            ..Default::default()
+        },
+    };
+    painter.handle_event(event, window)
+}
+
+pub fn mouse_button(
+    painter: &mut Painter,
+    window: *mut SDL_Window,
+    down: bool,
+    x: f32,
+    y: f32,
+) -> bool {
+    let r#type = if down {
+        SDL_EventType::MOUSE_BUTTON_DOWN
+    } else {
+        SDL_EventType::MOUSE_BUTTON_UP
+    };
+    let event = SDL_Event {
+        button: SDL_MouseButtonEvent {
+            r#type,
+            button: SDL_BUTTON_LEFT as u8,
+            down,
+            clicks: 1,
+            x,
+            y,
+            ..Default::default()
+        },
+    };
+    painter.handle_event(event, window)
+}
+
+/* Turns the wheel by `steps` with the pointer at `x`, `y`. */
+pub fn mouse_wheel(
+    painter: &mut Painter,
+    window: *mut SDL_Window,
+    steps: f32,
+    x: f32,
+    y: f32,
+) -> bool {
+    let event = SDL_Event {
+        wheel: SDL_MouseWheelEvent {
+            r#type: SDL_EventType::MOUSE_WHEEL,
+            y: steps,
+            mouse_x: x,
+            mouse_y: y,
+            ..Default::default()
+        },
+    };
+    painter.handle_event(event, window)
+}
+
+/* A window event of `type`, e.g. a resize with the new size in `data1` and `data2`. */
+pub fn window_event(
+    painter: &mut Painter,
+    window: *mut SDL_Window,
+    r#type: SDL_EventType,
+    data1: i32,
+    data2: i32,
+) -> bool {
+    let event = SDL_Event {
+        window: SDL_WindowEvent {
+            r#type,
+            data1,
+            data2,
+            ..Default::default()
+        },
+    };
+    painter.handle_event(event, window)
+}
+
+pub const NO_MOD: SDL_Keymod = SDL_KMOD_NONE;
@@ -0,0 +1,283 @@
+mod common;
+
+use common::{Headless, MockClipboard, NO_MOD, key_down, key_up, press, run, text_input};
+use egui_sdl3::{Painter, PainterBuilder};
+use sdl3_sys::keyboard::SDL_SetModState;
+use sdl3_sys::keycode::{
+    SDL_KMOD_LCTRL, SDL_KMOD_LSHIFT, SDL_KMOD_NONE, SDL_KMOD_NUM, SDL_Keycode, SDLK_A,
+    SDLK_BACKSPACE, SDLK_C, SDLK_DELETE, SDLK_K, SDLK_KP_8, SDLK_LCTRL, SDLK_LSHIFT, SDLK_TAB,
+    SDLK_V, SDLK_X, SDLK_Z,
+};
+
+const EDIT: &str = "edit";
+
+/* A text field that is focused from the first pass on. */
+fn text_edit(ctx: &egui::Context, text: &mut String) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        let response = ui.add(egui::TextEdit::singleline(text).id(egui::Id::new(EDIT)));
+        if !response.has_focus() {
+            response.request_focus();
+        }
+    });
+}
+
+/* A painter with a focused text field holding `text`. */
+fn focused(headless: &Headless, text: &mut String, frame: &mut f64) -> Painter {
+    focus(Painter::new(headless.window), text, frame)
+}
+
+fn focus(mut painter: Painter, text: &mut String, frame: &mut f64) -> Painter {
+    run(&mut painter, frame, |ctx| text_edit(ctx, text));
+    run(&mut painter, frame, |ctx| text_edit(ctx, text));
+    assert!(painter.context().wants_keyboard_input());
+    painter
+}
+
+#[test]
+fn select_all_replaces_text() {
+    let headless = Headless::new(320, 240);
+    let mut text = String::from("some text");
+    let mut frame = 0.0;
+    let mut painter = focused(&headless, &mut text, &mut frame);
+
+    press(&mut painter, headless.window, SDLK_A, SDL_KMOD_LCTRL);
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+    text_input(&mut painter, headless.window, "x");
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+
+    assert_eq!(text, "x");
+}
+
+#[test]
+fn undo_restores_text() {
+    let headless = Headless::new(320, 240);
+    let mut text = String::from("some");
+    let mut frame = 0.0;
+    let mut painter = focused(&headless, &mut text, &mut frame);
+
+    text_input(&mut painter, headless.window, " text");
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+    assert_eq!(text, "some text");
+    press(&mut painter, headless.window, SDLK_Z, SDL_KMOD_LCTRL);
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+
+    assert_eq!(text, "some");
+}
+
+#[test]
+fn ctrl_a_inserts_no_text() {
+    let headless = Headless::new(320, 240);
+    let mut text = String::from("some text");
+    let mut frame = 0.0;
+    let mut painter = focused(&headless, &mut text, &mut frame);
+
+    // Some platforms send the letter as text even while Ctrl is held
+    unsafe { SDL_SetModState(SDL_KMOD_LCTRL) };
+    key_down(&mut painter, headless.window, SDLK_A, SDL_KMOD_LCTRL);
+    text_input(&mut painter, headless.window, "a");
+    key_up(&mut painter, headless.window, SDLK_A, SDL_KMOD_LCTRL);
+    unsafe { SDL_SetModState(SDL_KMOD_NONE) };
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+
+    assert_eq!(text, "some text");
+}
+
+#[test]
+fn cut_removes_selection() {
+    let headless = Headless::new(320, 240);
+    let clipboard = MockClipboard::default();
+    let painter = PainterBuilder::new(headless.window)
+        .with_clipboard(clipboard.clone())
+        .build();
+    let mut text = String::from("some text");
+    let mut frame = 0.0;
+    let mut painter = focus(painter, &mut text, &mut frame);
+
+    press(&mut painter, headless.window, SDLK_A, SDL_KMOD_LCTRL);
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+    press(&mut painter, headless.window, SDLK_X, SDL_KMOD_LCTRL);
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+
+    assert_eq!(text, "");
+    assert_eq!(clipboard.0.borrow().as_deref(), Some("some text"));
+}
+
+#[test]
+fn copy_leaves_selection() {
+    let headless = Headless::new(320, 240);
+    let clipboard = MockClipboard::default();
+    let painter = PainterBuilder::new(headless.window)
+        .with_clipboard(clipboard.clone())
+        .build();
+    let mut text = String::from("some text");
+    let mut frame = 0.0;
+    let mut painter = focus(painter, &mut text, &mut frame);
+
+    press(&mut painter, headless.window, SDLK_A, SDL_KMOD_LCTRL);
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+    press(&mut painter, headless.window, SDLK_C, SDL_KMOD_LCTRL);
+    run(&mut painter, &mut frame, |ctx| text_edit(ctx, &mut text));
+
+    assert_eq!(text, "some text");
+    assert_eq!(clipboard.0.borrow().as_deref(), Some("some text"));
+}
+
+#[test]
+fn tab_focuses_a_widget() {
+    let headless = Headless::new(320, 240);
+    let mut painter = Painter::new(headless.window);
+    let mut frame = 0.0;
+    let mut text = String::from("some text");
+    let ui = |ctx: &egui::Context, text: &mut String| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.text_edit_singleline(text);
+        });
+    };
+    run(&mut painter, &mut frame, |ctx| ui(ctx, &mut text));
+    run(&mut painter, &mut frame, |ctx| ui(ctx, &mut text));
+    assert!(painter.context().memory(|m| m.focused()).is_none());
+
+    // Nothing has keyboard focus yet, Tab still has to reach egui
+    press(&mut painter, headless.window, SDLK_TAB, NO_MOD);
+    run(&mut painter, &mut frame, |ctx| ui(ctx, &mut text));
+    run(&mut painter, &mut frame, |ctx| ui(ctx, &mut text));
+
+    assert!(painter.context().memory(|m| m.focused()).is_some());
+}
+
+/* Pastes `pasted` into an empty multiline text field through a mock clipboard. */
+fn paste(pasted: &str) -> String {
+    let headless = Headless::new(320, 240);
+    let clipboard = MockClipboard::default();
+    *clipboard.0.borrow_mut() = Some(pasted.to_owned());
+    let mut painter = PainterBuilder::new(headless.window)
+        .with_clipboard(clipboard)
+        .build();
+    let mut text = String::new();
+    let mut frame = 0.0;
+    let ui = |ctx: &egui::Context, text: &mut String| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let edit = egui::TextEdit::multiline(text).id(egui::Id::new(EDIT));
+            let response = ui.add(edit);
+            if !response.has_focus() {
+                response.request_focus();
+            }
+        });
+    };
+    run(&mut painter, &mut frame, |ctx| ui(ctx, &mut text));
+    run(&mut painter, &mut frame, |ctx| ui(ctx, &mut text));
+
+    press(&mut painter, headless.window, SDLK_V, SDL_KMOD_LCTRL);
+    run(&mut painter, &mut frame, |ctx| ui(ctx, &mut text));
+    text
+}
+
+#[test]
+fn paste_keeps_lines_and_unicode() {
+    assert_eq!(paste("grüße\n日本語 🦀\nend"), "grüße\n日本語 🦀\nend");
+}
+
+#[test]
+fn paste_turns_crlf_and_cr_into_newlines() {
+    assert_eq!(
+        paste("windows\r\nold mac\rend\r\n"),
+        "windows\nold mac\nend\n"
+    );
+}
+
+/* The key presses egui received in the next pass, with their modifiers. */
+fn pressed_keys(
+    painter: &mut Painter,
+    frame: &mut f64,
+    text: &mut String,
+) -> Vec<(egui::Key, egui::Modifiers)> {
+    let mut keys = Vec::new();
+    run(painter, frame, |ctx| {
+        keys = ctx.input(|i| {
+            i.events
+                .iter()
+                .filter_map(|event| match event {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+                .collect()
+        });
+        text_edit(ctx, text);
+    });
+    keys
+}
+
+#[test]
+fn modifiers_held_one_after_another_combine() {
+    let headless = Headless::new(320, 240);
+    let mut text = String::new();
+    let mut frame = 0.0;
+    let mut painter = focused(&headless, &mut text, &mut frame);
+
+    let both = SDL_KMOD_LCTRL | SDL_KMOD_LSHIFT;
+    key_down(&mut painter, headless.window, SDLK_LCTRL, SDL_KMOD_LCTRL);
+    key_down(&mut painter, headless.window, SDLK_LSHIFT, both);
+    key_down(&mut painter, headless.window, SDLK_K, both);
+    let keys = pressed_keys(&mut painter, &mut frame, &mut text);
+
+    let (key, modifiers) = keys.last().copied().unwrap();
+    assert_eq!(key, egui::Key::K);
+    assert!(modifiers.ctrl && modifiers.shift);
+}
+
+#[test]
+fn keypad_uses_num_lock_of_the_event() {
+    let headless = Headless::new(320, 240);
+    let mut text = String::new();
+    let mut frame = 0.0;
+    let mut painter = focused(&headless, &mut text, &mut frame);
+
+    // SDL's global state may already have moved on when the event is handled
+    unsafe { SDL_SetModState(SDL_KMOD_NONE) };
+    key_down(&mut painter, headless.window, SDLK_KP_8, SDL_KMOD_NUM);
+    let keys = pressed_keys(&mut painter, &mut frame, &mut text);
+    assert_eq!(keys.last().map(|(key, _)| *key), Some(egui::Key::Num8));
+
+    unsafe { SDL_SetModState(SDL_KMOD_NUM) };
+    key_down(&mut painter, headless.window, SDLK_KP_8, SDL_KMOD_NONE);
+    unsafe { SDL_SetModState(SDL_KMOD_NONE) };
+    let keys = pressed_keys(&mut painter, &mut frame, &mut text);
+    assert_eq!(keys.last().map(|(key, _)| *key), Some(egui::Key::ArrowUp));
+}
+
+fn code_editor(ctx: &egui::Context, text: &mut String) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        let response = ui.code_editor(text);
+        if !response.has_focus() {
+            response.request_focus();
+        }
+    });
+}
+
+/* Selects everything in a code editor holding two lines and presses `key`. */
+fn delete_all(key: SDL_Keycode) -> String {
+    let headless = Headless::new(320, 240);
+    let mut painter = Painter::new(headless.window);
+    let mut text = String::from("fn main() {\n    println!(\"hi\");\n}");
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, |ctx| code_editor(ctx, &mut text));
+    run(&mut painter, &mut frame, |ctx| code_editor(ctx, &mut text));
+    assert!(painter.context().wants_keyboard_input());
+
+    press(&mut painter, headless.window, SDLK_A, SDL_KMOD_LCTRL);
+    run(&mut painter, &mut frame, |ctx| code_editor(ctx, &mut text));
+    press(&mut painter, headless.window, key, NO_MOD);
+    run(&mut painter, &mut frame, |ctx| code_editor(ctx, &mut text));
+    text
+}
+
+#[test]
+fn select_all_and_delete_empties_code_editor() {
+    assert_eq!(delete_all(SDLK_DELETE), "");
+    assert_eq!(delete_all(SDLK_BACKSPACE), "");
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use common::{Headless, run};
+use egui_sdl3::Painter;
+use sdl3_sys::pixels::SDL_PIXELFORMAT_RGBA32;
+use sdl3_sys::rect::{SDL_FRect, SDL_Rect};
+use sdl3_sys::render::{
+    SDL_CreateTexture, SDL_DestroyTexture, SDL_RenderClear, SDL_RenderFillRect,
+    SDL_RenderReadPixels, SDL_Renderer, SDL_SetRenderDrawColor, SDL_SetRenderTarget,
+    SDL_TEXTUREACCESS_TARGET, SDL_Texture,
+};
+use sdl3_sys::surface::{SDL_DestroySurface, SDL_ReadSurfacePixel};
+use std::ptr;
+
+fn window(ctx: &egui::Context) {
+    egui::Window::new("Window")
+        .fixed_pos((20.0, 20.0))
+        .show(ctx, |ui| ui.label("Some text"));
+}
+
+/* The color of the pixel at `x`, `y` of the current render target. */
+fn pixel(renderer: *mut SDL_Renderer, x: i32, y: i32) -> (u8, u8, u8, u8) {
+    let (mut r, mut g, mut b, mut a) = (0, 0, 0, 0);
+    unsafe {
+        let rect = SDL_Rect { x, y, w: 1, h: 1 };
+        let surface = SDL_RenderReadPixels(renderer, &rect);
+        assert!(!surface.is_null(), "SDL_RenderReadPixels failed");
+        SDL_ReadSurfacePixel(surface, 0, 0, &mut r, &mut g, &mut b, &mut a);
+        SDL_DestroySurface(surface);
+    }
+    (r, g, b, a)
+}
+
+/* A texture of the size of the test window, set as the render target and cleared to `color`. */
+fn render_target(renderer: *mut SDL_Renderer, (r, g, b): (u8, u8, u8)) -> *mut SDL_Texture {
+    unsafe {
+        let target = SDL_CreateTexture(
+            renderer,
+            SDL_PIXELFORMAT_RGBA32,
+            SDL_TEXTUREACCESS_TARGET,
+            400,
+            300,
+        );
+        assert!(!target.is_null(), "SDL_CreateTexture failed");
+        SDL_SetRenderTarget(renderer, target);
+        SDL_SetRenderDrawColor(renderer, r, g, b, 255);
+        SDL_RenderClear(renderer);
+        target
+    }
+}
+
+fn release_target(renderer: *mut SDL_Renderer, target: *mut SDL_Texture) {
+    unsafe {
+        SDL_SetRenderTarget(renderer, ptr::null_mut());
+        SDL_DestroyTexture(target);
+    }
+}
+
+#[test]
+fn egui_leaves_the_scene_around_it_untouched() {
+    let headless = Headless::new(400, 300);
+    let renderer = headless.renderer;
+    let mut painter = Painter::new(headless.window);
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, window);
+    run(&mut painter, &mut frame, window);
+
+    unsafe {
+        let target = render_target(renderer, (0, 0, 255));
+        // The application's scene, partly behind the egui window
+        let quad = SDL_FRect {
+            x: 10.0,
+            y: 10.0,
+            w: 380.0,
+            h: 280.0,
+        };
+        SDL_SetRenderDrawColor(renderer, 255, 0, 0, 255);
+        SDL_RenderFillRect(renderer, &quad);
+
+        painter.draw(renderer);
+
+        assert_eq!(pixel(renderer, 5, 5), (0, 0, 255, 255));
+        assert_eq!(pixel(renderer, 15, 15), (255, 0, 0, 255));
+        assert_eq!(pixel(renderer, 385, 285), (255, 0, 0, 255));
+        assert_ne!(pixel(renderer, 30, 30), (255, 0, 0, 255));
+
+        release_target(renderer, target);
+    }
+}
+
+#[test]
+fn shapes_other_than_rects_are_drawn() {
+    let headless = Headless::new(400, 300);
+    let renderer = headless.renderer;
+    let mut painter = Painter::new(headless.window);
+    let triangle = |ctx: &egui::Context| {
+        let points = vec![
+            egui::pos2(100.0, 100.0),
+            egui::pos2(200.0, 100.0),
+            egui::pos2(100.0, 200.0),
+        ];
+        ctx.layer_painter(egui::LayerId::background())
+            .add(egui::Shape::convex_polygon(
+                points,
+                egui::Color32::GREEN,
+                egui::Stroke::NONE,
+            ));
+    };
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, triangle);
+
+    let target = render_target(renderer, (0, 0, 0));
+    painter.draw(renderer);
+
+    // Only drawn if probing for geometry support found it, the rect fallback drops it
+    assert_eq!(pixel(renderer, 120, 120), (0, 255, 0, 255));
+    release_target(renderer, target);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{Headless, mouse_button, mouse_motion, mouse_wheel, run};
+use egui_sdl3::Painter;
+use sdl3_sys::keyboard::SDL_SetModState;
+use sdl3_sys::keycode::{SDL_KMOD_LCTRL, SDL_KMOD_NONE};
+
+fn window(ctx: &egui::Context) {
+    egui::Window::new("Window")
+        .fixed_pos((20.0, 20.0))
+        .show(ctx, |ui| ui.label("Some text"));
+}
+
+/* Moves the pointer to `x`, `y`, lets egui see it there and clicks. Returns whether the press and
+ * the release were handled. */
+fn click(
+    painter: &mut Painter,
+    headless: &Headless,
+    frame: &mut f64,
+    x: f32,
+    y: f32,
+) -> (bool, bool) {
+    mouse_motion(painter, headless.window, x, y);
+    run(painter, frame, window);
+    let pressed = mouse_button(painter, headless.window, true, x, y);
+    run(painter, frame, window);
+    let released = mouse_button(painter, headless.window, false, x, y);
+    run(painter, frame, window);
+    (pressed, released)
+}
+
+#[test]
+fn click_on_window_is_handled() {
+    let headless = Headless::new(400, 300);
+    let mut painter = Painter::new(headless.window);
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, window);
+    run(&mut painter, &mut frame, window);
+
+    assert_eq!(
+        click(&mut painter, &headless, &mut frame, 40.0, 40.0),
+        (true, true)
+    );
+}
+
+#[test]
+fn click_on_empty_space_is_not_handled() {
+    let headless = Headless::new(400, 300);
+    let mut painter = Painter::new(headless.window);
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, window);
+    run(&mut painter, &mut frame, window);
+
+    assert_eq!(
+        click(&mut painter, &headless, &mut frame, 350.0, 250.0),
+        (false, false)
+    );
+}
+
+/* Whether a Ctrl+wheel step over the window reached egui as a zoom. */
+fn ctrl_wheel_zooms(ctrl_is_command: bool) -> bool {
+    let headless = Headless::new(400, 300);
+    let mut painter = Painter::new(headless.window);
+    painter.set_treat_ctrl_as_command(ctrl_is_command);
+    let mut frame = 0.0;
+    mouse_motion(&mut painter, headless.window, 40.0, 40.0);
+    run(&mut painter, &mut frame, window);
+    run(&mut painter, &mut frame, window);
+
+    unsafe { SDL_SetModState(SDL_KMOD_LCTRL) };
+    mouse_wheel(&mut painter, headless.window, 1.0, 40.0, 40.0);
+    unsafe { SDL_SetModState(SDL_KMOD_NONE) };
+    let mut zoomed = false;
+    run(&mut painter, &mut frame, |ctx| {
+        zoomed = ctx.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::Zoom(_))));
+        window(ctx);
+    });
+    zoomed
+}
+
+#[test]
+fn ctrl_wheel_zooms_while_ctrl_is_command() {
+    assert_eq!(ctrl_wheel_zooms(true), !cfg!(target_os = "macos"));
+}
+
+#[test]
+fn ctrl_wheel_scrolls_while_ctrl_is_not_command() {
+    assert!(!ctrl_wheel_zooms(false));
+}
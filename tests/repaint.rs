@@ -0,0 +1,63 @@
+mod common;
+
+use common::{Headless, mouse_button, mouse_motion, run};
+use egui_sdl3::Painter;
+
+fn checkbox(ctx: &egui::Context, checked: &mut bool) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.add_sized([100.0, 20.0], egui::Checkbox::new(checked, "Check"));
+    });
+}
+
+/* Runs and draws passes until egui has nothing left to repaint, e.g. once an animation ended. */
+fn settle(painter: &mut Painter, headless: &Headless, frame: &mut f64, checked: &mut bool) {
+    for _ in 0..120 {
+        run(painter, frame, |ctx| checkbox(ctx, checked));
+        if !painter.needs_repaint() {
+            return;
+        }
+        painter.draw(headless.renderer);
+    }
+    panic!("egui kept repainting");
+}
+
+#[test]
+fn toggling_a_checkbox_needs_a_repaint() {
+    let headless = Headless::new(320, 240);
+    let mut painter = Painter::new(headless.window);
+    let mut checked = false;
+    let mut frame = 0.0;
+    settle(&mut painter, &headless, &mut frame, &mut checked);
+    // Hovering changes how the checkbox looks
+    mouse_motion(&mut painter, headless.window, 20.0, 18.0);
+    settle(&mut painter, &headless, &mut frame, &mut checked);
+
+    mouse_button(&mut painter, headless.window, true, 20.0, 18.0);
+    run(&mut painter, &mut frame, |ctx| checkbox(ctx, &mut checked));
+    mouse_button(&mut painter, headless.window, false, 20.0, 18.0);
+    run(&mut painter, &mut frame, |ctx| checkbox(ctx, &mut checked));
+
+    assert!(checked);
+    assert!(painter.needs_repaint());
+    settle(&mut painter, &headless, &mut frame, &mut checked);
+    assert!(!painter.needs_repaint());
+}
+
+#[test]
+fn taken_primitives_count_as_drawn() {
+    let headless = Headless::new(320, 240);
+    let mut painter = Painter::new(headless.window);
+    let mut checked = false;
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, |ctx| checkbox(ctx, &mut checked));
+
+    // A custom renderer that never calls `draw`
+    for _ in 0..120 {
+        assert!(painter.take_primitives().is_some());
+        run(&mut painter, &mut frame, |ctx| checkbox(ctx, &mut checked));
+        if !painter.needs_repaint() {
+            return;
+        }
+    }
+    panic!("egui kept repainting");
+}
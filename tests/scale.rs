@@ -0,0 +1,124 @@
+mod common;
+
+use common::{Headless, run, window_event};
+use egui_sdl3::Painter;
+use sdl3_sys::events::SDL_EventType;
+use sdl3_sys::hints::{SDL_HINT_MOUSE_DOUBLE_CLICK_RADIUS, SDL_ResetHint, SDL_SetHint};
+
+/* A painter for a window of 320x240 points on a display with two pixels per point. */
+fn scaled(headless: &Headless) -> Painter {
+    let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(320.0, 240.0));
+    let mut raw_input = egui::RawInput {
+        screen_rect: Some(screen_rect),
+        ..Default::default()
+    };
+    raw_input
+        .viewports
+        .entry(egui::ViewportId::ROOT)
+        .or_default()
+        .native_pixels_per_point = Some(2.0);
+    Painter::with_raw_input(headless.window, raw_input)
+}
+
+#[test]
+fn screen_rect_is_window_size_after_new() {
+    let headless = Headless::new(320, 240);
+    let mut painter = Painter::new(headless.window);
+    let expected = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(320.0, 240.0));
+    assert_eq!(painter.screen_rect_points(), expected);
+
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, |ctx| {
+        assert_eq!(ctx.screen_rect(), expected);
+    });
+}
+
+#[test]
+fn screen_rect_is_in_points_on_scaled_display() {
+    let headless = Headless::new(320, 240);
+    let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(320.0, 240.0));
+    let mut painter = scaled(&headless);
+    assert_eq!(painter.screen_rect_points(), screen_rect);
+    assert_eq!(painter.screen_size_pixels(), (640, 480));
+
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, |ctx| {
+        assert_eq!(ctx.screen_rect(), screen_rect);
+        assert_eq!(ctx.pixels_per_point(), 2.0);
+    });
+}
+
+#[test]
+fn resize_on_scaled_display_keeps_points() {
+    let headless = Headless::new(320, 240);
+    let mut painter = scaled(&headless);
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, |_| {});
+
+    // SDL sends the logical size first, then the size in pixels
+    let expected = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(400.0, 300.0));
+    let (resized, pixel_size_changed) = (
+        SDL_EventType::WINDOW_RESIZED,
+        SDL_EventType::WINDOW_PIXEL_SIZE_CHANGED,
+    );
+    window_event(&mut painter, headless.window, resized, 400, 300);
+    assert_eq!(painter.screen_rect_points(), expected);
+    window_event(&mut painter, headless.window, pixel_size_changed, 800, 600);
+    assert_eq!(painter.screen_rect_points(), expected);
+    assert_eq!(painter.screen_size_pixels(), (800, 600));
+    run(&mut painter, &mut frame, |ctx| {
+        assert_eq!(ctx.screen_rect(), expected);
+        assert_eq!(ctx.pixels_per_point(), 2.0);
+    });
+}
+
+#[test]
+fn display_scale_change_updates_pixels_per_point() {
+    let headless = Headless::new(320, 240);
+    let mut painter = scaled(&headless);
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, |_| {});
+    painter.draw(headless.renderer);
+    run(&mut painter, &mut frame, |_| {});
+    assert!(!painter.needs_repaint());
+
+    // As if the window moved from the display with two pixels per point to the dummy driver's
+    // display, which has one
+    window_event(
+        &mut painter,
+        headless.window,
+        SDL_EventType::WINDOW_DISPLAY_SCALE_CHANGED,
+        0,
+        0,
+    );
+    run(&mut painter, &mut frame, |ctx| {
+        assert_eq!(ctx.pixels_per_point(), 1.0);
+    });
+    assert!(painter.needs_repaint());
+}
+
+#[test]
+fn double_click_radius_follows_the_scale() {
+    let headless = Headless::new(320, 240);
+    unsafe { SDL_SetHint(SDL_HINT_MOUSE_DOUBLE_CLICK_RADIUS, c"12".as_ptr()) };
+    let mut painter = scaled(&headless);
+    unsafe { SDL_ResetHint(SDL_HINT_MOUSE_DOUBLE_CLICK_RADIUS) };
+    let max_click_dist = |painter: &Painter| {
+        painter
+            .context()
+            .options(|o| o.input_options.max_click_dist)
+    };
+
+    // The radius is in window coordinates, which are points here
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, |_| {});
+    assert_eq!(max_click_dist(&painter), 12.0);
+    painter.context().set_zoom_factor(2.0);
+    run(&mut painter, &mut frame, |_| {});
+    run(&mut painter, &mut frame, |_| {});
+    assert_eq!(max_click_dist(&painter), 6.0);
+
+    painter.set_drag_threshold(3.0);
+    run(&mut painter, &mut frame, |_| {});
+    assert_eq!(max_click_dist(&painter), 3.0);
+}
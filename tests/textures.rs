@@ -0,0 +1,144 @@
+mod common;
+
+use common::{Headless, run};
+use egui_sdl3::{AlphaMode, Painter};
+use sdl3_sys::events::{SDL_Event, SDL_EventType, SDL_RenderEvent};
+
+const LINES: [(&str, f32); 4] = [
+    ("The quick brown fox", 14.0),
+    ("jumps over the lazy dog", 23.0),
+    ("PACK MY BOX WITH FIVE", 31.0),
+    ("0123456789 !?#%&", 39.0),
+];
+
+fn lines(ui: &mut egui::Ui, count: usize) {
+    for (text, size) in &LINES[..count] {
+        ui.label(egui::RichText::new(*text).size(*size));
+    }
+}
+
+#[test]
+fn atlas_updates_match_a_single_upload() {
+    let headless = Headless::new(480, 240);
+
+    // Each new size and set of glyphs grows the font atlas that is already on the GPU
+    let mut painter = Painter::new(headless.window);
+    let mut frame = 0.0;
+    for count in 1..=LINES.len() {
+        run(&mut painter, &mut frame, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| lines(ui, count));
+        });
+        painter.draw(headless.renderer);
+        if count > 1 {
+            assert!(painter.last_frame_stats().textures_updated > 0);
+        }
+    }
+    run(&mut painter, &mut frame, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| lines(ui, LINES.len()));
+    });
+    let updated = painter.capture_to_image(headless.renderer).unwrap();
+
+    // The same text with its glyphs in one upload
+    let mut fresh = Painter::new(headless.window);
+    let mut frame = 0.0;
+    run(&mut fresh, &mut frame, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| lines(ui, LINES.len()));
+    });
+    fresh.draw(headless.renderer);
+    run(&mut fresh, &mut frame, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| lines(ui, LINES.len()));
+    });
+    let expected = fresh.capture_to_image(headless.renderer).unwrap();
+
+    assert_eq!(updated.size, expected.size);
+    assert!(
+        updated.pixels == expected.pixels,
+        "glyphs differ after partial atlas updates"
+    );
+}
+
+#[test]
+fn device_reset_drops_registered_textures() {
+    let headless = Headless::new(64, 64);
+    let mut painter = Painter::new(headless.window);
+    let image = egui::ColorImage::new([4, 4], vec![egui::Color32::RED; 16]);
+    let id = painter
+        .register_texture(headless.renderer, &image, None, AlphaMode::Premultiplied)
+        .unwrap();
+    assert!(painter.has_texture(id));
+
+    let event = SDL_Event {
+        render: SDL_RenderEvent {
+            r#type: SDL_EventType::RENDER_DEVICE_RESET,
+            ..Default::default()
+        },
+    };
+    painter.handle_event(event, headless.window);
+    // The application has to register it again
+    assert!(!painter.has_texture(id));
+}
+
+#[test]
+fn budgeted_uploads_finish_in_a_lazy_loop() {
+    let headless = Headless::new(320, 240);
+    let mut painter = Painter::new(headless.window);
+    // Every texture is uploaded in a frame of its own
+    painter.set_upload_budget(Some(1));
+    let mut textures = Vec::new();
+    let ui = |ctx: &egui::Context, textures: &mut Vec<egui::TextureHandle>| {
+        if textures.is_empty() {
+            for i in 0..3 {
+                let image = egui::ColorImage::new([8, 8], vec![egui::Color32::RED; 64]);
+                textures.push(ctx.load_texture(format!("image {i}"), image, Default::default()));
+            }
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            for texture in textures.iter() {
+                ui.image((texture.id(), egui::vec2(8.0, 8.0)));
+            }
+        });
+    };
+
+    // Like an application that only draws when needed and otherwise sleeps until
+    // `repaint_after`, forever while nothing is due
+    let mut frame = 0.0;
+    for _ in 0..20 {
+        run(&mut painter, &mut frame, |ctx| ui(ctx, &mut textures));
+        if painter.needs_repaint() {
+            painter.draw(headless.renderer);
+        } else if painter.repaint_after() == std::time::Duration::MAX {
+            break;
+        }
+    }
+
+    assert!(painter.frame_complete());
+}
+
+#[test]
+fn evicted_texture_is_not_drawn() {
+    let headless = Headless::new(64, 64);
+    let renderer = headless.renderer;
+    let mut painter = Painter::new(headless.window);
+    let image = egui::ColorImage::new([4, 4], vec![egui::Color32::RED; 16]);
+    let id = painter
+        .register_texture(renderer, &image, None, AlphaMode::Premultiplied)
+        .unwrap();
+    let ui = |ctx: &egui::Context| {
+        ctx.layer_painter(egui::LayerId::background()).image(
+            id,
+            egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(32.0, 32.0)),
+            egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)),
+            egui::Color32::WHITE,
+        );
+    };
+    let mut frame = 0.0;
+    run(&mut painter, &mut frame, ui);
+    painter.draw(renderer);
+    painter.set_user_texture_budget(Some(0));
+    assert!(!painter.has_texture(id));
+
+    run(&mut painter, &mut frame, ui);
+    let image = painter.capture_to_image(renderer).unwrap();
+    // Drawn without its texture, the image would be a white box
+    assert_eq!(image.pixels[16 * 64 + 16], egui::Color32::TRANSPARENT);
+}